@@ -1,3 +1,4 @@
+use crossterm::style::Color;
 use serde::{Deserialize, Serialize};
 
 use crate::Position;
@@ -6,8 +7,49 @@ use crate::Task;
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Block {
+    pub id: u64, // Unique on its board and never reused, shared with task IDs
     pub title: String,
+    #[serde(skip)]
     pub position: Position,
+    #[serde(skip)]
     pub size: Size,
+    #[serde(skip)]
+    pub scroll: usize, // First task in view
+    #[serde(rename = "tasks")]
     pub task_list: Vec<Task>,
 }
+
+impl Block {
+    pub fn new(id: u64, title: String) -> Self {
+        Self {
+            id,
+            title,
+            position: Position { x: 0, y: 0 },
+            size: Size {
+                width: 0,
+                height: 0,
+            },
+            scroll: 0,
+            task_list: vec![],
+        }
+    }
+
+    // Number of tasks that fit inside the border, each one takes four rows and the last one
+    // still needs its description row above the bottom border
+    pub fn visible_tasks(&self) -> usize {
+        (self.size.height.saturating_sub(1) / 4) as usize
+    }
+
+    // Border colour of the block at the given column index
+    pub fn color(index: usize) -> Color {
+        let palette = [
+            Color::Red,
+            Color::Yellow,
+            Color::Green,
+            Color::Cyan,
+            Color::Blue,
+            Color::Magenta,
+        ];
+        palette[index % palette.len()]
+    }
+}
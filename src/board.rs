@@ -1,49 +1,142 @@
 use std::env;
-use std::fs::File;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use chrono::{DateTime, Days, Local, NaiveDate};
 
 use crossterm::{
     cursor,
-    event::{read, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
-    style::{Color, Stylize},
+    event::{poll, read, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    style::Color,
 };
-use std::io::Write;
 
-use serde_json::json;
-use serde_json::Value;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
+use crate::flow;
+use crate::initials;
+use crate::list_backups;
+use crate::parse_due;
+use crate::parse_list;
+use crate::urgency;
+use crate::wrap_text;
+use crate::write_atomic;
 use crate::Block;
+use crate::BoardDocument;
+use crate::BoardError;
+use crate::ChecklistItem;
+use crate::Config;
+use crate::EditorAction;
+use crate::History;
+use crate::LineEditor;
 use crate::Position;
+use crate::Priority;
 use crate::Selector;
-use crate::Size;
+use crate::Snapshot;
+use crate::Stats;
 use crate::Task;
+use crate::TaskEvent;
 use crate::TaskStatus;
 use crate::TerminalW;
-
-#[derive(PartialEq)]
+use crate::TextEditor;
+use crate::Urgency;
+
+const TITLE_LIMIT: usize = 15;
+const PATH_LIMIT: usize = 4096;
+const TAGS_LIMIT: usize = 64;
+const ITEM_LIMIT: usize = 40;
+// Events of a task shown in its popup, the most recent ones
+const HISTORY_ROWS: usize = 4;
+const TIME_FORMAT: &str = "%b %d %H:%M";
+// Lengths in days the flow diagram steps through with + and -, the last one is also the
+// longest range that can be picked
+const FLOW_SPANS: [i64; 7] = [7, 14, 30, 60, 90, 180, 365];
+
+const UNTITLED: &str = "Untitled";
+
+// Smallest terminal the board can be drawn in
+const MIN_WIDTH: u16 = 40;
+const MIN_HEIGHT: u16 = 14;
+
+#[derive(PartialEq, Clone, Copy)]
 pub enum InputMode {
     Command,
     WritingTask,
+    EditingTask,
     WritingBoard,
+    WritingPath,
+    WritingBlock,
+    RenamingBlock,
+    WritingFilter,
+    WritingMembers,
+    WritingItem,
+    WritingBlockers,
+    WritingExport,
+    WritingRange,
+    ConfirmingQuit,
+    RestoringBackup,
+    ViewingAgenda,
+    ViewingChecklist,
+    ViewingLinks,
+    ViewingHistory,
+    ViewingStats,
+    ViewingFlow,
 }
 #[derive(PartialEq)]
+#[allow(clippy::enum_variant_names)]
 pub enum WritingTaskType {
     WritingTitle,
+    WritingDue,
+    WritingTags,
+    WritingAssignees,
     WritingDescription,
 }
 
 pub struct Board {
     quit: bool,
+    quit_after_save: bool,
+    terminate: Arc<AtomicBool>, // Set by SIGTERM/SIGHUP
+    config: Config,
     term: TerminalW, // Terminal reference
     is_modified: bool,
-
-    board_name: String,
-    block_list: [Block; 3],
+    history: History,
+    revision: u64,                       // Revision the board is currently at
+    last_revision: u64,                  // Highest revision handed out so far
+    saved_revision: u64, // Revision in the board file, a new board starts out as saved
+    last_change: Instant, // Time of the last mutation, for autosave
+    last_saved: Option<DateTime<Local>>, // Shown in the bottom bar
+    autosave_failed: bool, // Stops retrying until the next change
+
+    board_name: String,         // Name shown in the title
+    members: Vec<String>,       // Roster tasks can be assigned from
+    next_id: u64,               // ID of the next new task or block
+    file_path: Option<PathBuf>, // Where the board is saved, None until the first save
+    block_list: Vec<Block>,
+    block_scroll: usize, // First block on screen when they don't all fit
+    blocks_shown: usize, // Number of blocks on screen
     selector: Selector,
+    filter: Vec<String>, // Tags a task needs one of to be shown, empty shows every task
+    only_mine: bool,     // Show only the tasks assigned to `user`
+    user: Option<String>, // Current user, from the config or $USER
 
     input_state: InputMode, // Input Mode the user is in
     showing_task: bool,
-    writing_string: String,
+    task_scroll: usize,
+    checklist_ptr: usize,     // Checklist item selected in the task popup
+    item_edit: Option<usize>, // Item being rewritten, None while writing a new one
+    links_ptr: usize,         // Linked task selected in the task popup
+    history_scroll: usize,    // Events scrolled back from the latest in the task popup
+    flow_days: i64,           // Days shown in the flow diagram
+    flow_end: NaiveDate,      // Last day shown in the flow diagram
+    status: Option<String>,   // Message shown under the bottom bar until the next key
+    backup_list: Vec<(PathBuf, SystemTime)>,
+    backup_ptr: usize,
+    agenda_ptr: usize,
+    line_editor: LineEditor,
+    text_editor: TextEditor,
     writing_count: WritingTaskType,
 
     tmp_task: Task,
@@ -51,94 +144,101 @@ pub struct Board {
 
 impl Board {
     pub fn default() -> Self {
-        let args: Vec<String> = env::args().collect();
-
-        let mut tmp_filepath = String::from("");
-        let mut tmp_filename = String::from("NONE");
-
-        let spacing = 2;
-
-        let starting_x = 15;
-        let starting_y = 5;
-
-        let default_width = 30;
-        let default_height = 25;
+        let file_path: Option<PathBuf> = env::args().nth(1).map(PathBuf::from);
 
-        if args.len() > 1 {
-            // LOADING!
-
-            let file_arg: String = args[1].clone();
-
-            let p = Path::new(&file_arg);
-
-            tmp_filepath = p.parent().unwrap().to_str().unwrap().to_string();
-
-            tmp_filename = p.file_name().unwrap().to_str().unwrap().to_string();
-        }
+        let board_name = file_path
+            .as_ref()
+            .and_then(|p| p.file_stem())
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| UNTITLED.to_string());
 
         Self {
             quit: false,
+            quit_after_save: false,
+            terminate: Arc::new(AtomicBool::new(false)),
+            config: Config::default(),
             term: TerminalW::default().expect("Failure to initialize."),
-            is_modified: true,
-            board_name: tmp_filename.to_string(),
-            block_list: [
-                Block {
-                    title: "To Do".to_string(),
-                    position: Position {
-                        x: starting_x,
-                        y: starting_y,
-                    },
-                    size: Size {
-                        width: default_width,
-                        height: default_height,
-                    },
-                    task_list: vec![],
-                },
-                Block {
-                    title: "Active".to_string(),
-                    position: Position {
-                        x: starting_x + spacing + default_width,
-                        y: starting_y,
-                    },
-                    size: Size {
-                        width: default_width,
-                        height: default_height,
-                    },
-                    task_list: vec![],
-                },
-                Block {
-                    title: "Completed".to_string(),
-                    position: Position {
-                        x: starting_x + (spacing * 2) + (default_width * 2),
-                        y: starting_y,
-                    },
-                    size: Size {
-                        width: default_width,
-                        height: default_height,
-                    },
-                    task_list: vec![],
-                },
+            is_modified: false,
+            history: History::default(),
+            revision: 0,
+            last_revision: 0,
+            saved_revision: 0,
+            last_change: Instant::now(),
+            last_saved: None,
+            autosave_failed: false,
+            board_name,
+            members: vec![],
+            next_id: 4,
+            file_path,
+            block_list: vec![
+                Block::new(1, "To Do".to_string()),
+                Block::new(2, "Active".to_string()),
+                Block::new(3, "Completed".to_string()),
             ],
+            block_scroll: 0,
+            blocks_shown: 0,
             selector: Selector::default(),
+            filter: vec![],
+            only_mine: false,
+            user: None,
             input_state: InputMode::Command,
             showing_task: false,
-            writing_string: String::from(""),
+            task_scroll: 0,
+            checklist_ptr: 0,
+            item_edit: None,
+            links_ptr: 0,
+            history_scroll: 0,
+            flow_days: 30,
+            flow_end: Local::now().date_naive(),
+            status: None,
+            backup_list: vec![],
+            backup_ptr: 0,
+            agenda_ptr: 0,
+            line_editor: LineEditor::new(TITLE_LIMIT),
+            text_editor: TextEditor::new(),
             writing_count: WritingTaskType::WritingTitle,
             tmp_task: Task {
+                id: 0,
                 status: TaskStatus::Todo,
+                priority: Priority::Normal,
                 title: "".to_string(),
                 description: "".to_string(),
+                due: None,
+                tags: vec![],
+                assignees: vec![],
+                checklist: vec![],
+                blocked_by: vec![],
+                created: None,
+                updated: None,
+                history: vec![],
             },
         }
     }
 
-    pub fn run(&mut self) {
-        //Set Size
-        self.term.set_size(128, 36);
+    pub fn run(&mut self) -> Result<(), BoardError> {
+        match Config::load() {
+            Ok(config) => self.config = config,
+            Err(e) => self.status = Some(format!("Could not read the config file: {}", e)),
+        }
+        self.user = self
+            .config
+            .user
+            .clone()
+            .or_else(|| env::var("USER").ok())
+            .filter(|user| !user.is_empty());
+
+        //A path that doesn't exist yet starts a new board that will be saved there
+        if self.file_path.as_ref().is_some_and(|p| p.exists()) {
+            self.load()?;
+        }
 
-        //Not a good solution
-        if self.board_name.ne("NONE") {
-            self.load();
+        //Changes saved when an earlier session was closed are left for the user to look at
+        let recovery = self.recovery_path();
+        if recovery.exists() {
+            self.status = Some(format!(
+                "Unsaved changes of a closed session are in {}",
+                recovery.display()
+            ));
         }
 
         //Set Title
@@ -147,193 +247,1294 @@ impl Board {
         self.term
             .set_cursor_shape(cursor::SetCursorStyle::BlinkingBlock);
 
-        self.term.clear_screen();
-        self.term.flush().unwrap();
+        //Closing the terminal or killing the process shouldn't lose unsaved work
+        let _ =
+            signal_hook::flag::register(signal_hook::consts::SIGTERM, Arc::clone(&self.terminate));
+        #[cfg(unix)]
+        let _ =
+            signal_hook::flag::register(signal_hook::consts::SIGHUP, Arc::clone(&self.terminate));
 
         loop {
             if self.quit {
-                self.term.clear_screen();
                 break;
             } else {
-                self.draw_board();
-                self.update();
+                self.term.clear_screen();
+                if self.term_too_small() {
+                    self.put_too_small();
+                } else {
+                    self.draw_board();
+                    self.update();
+                }
+                //A terminal that can't be drawn on any more is gone, as after a hangup
+                if self.term.present().is_err() {
+                    self.terminate_quit();
+                    break;
+                }
 
                 self.process_input();
-
-                self.term.flush().unwrap();
             }
         }
+
+        Ok(())
     }
 
     pub fn update(&mut self) {
-        if self.is_modified == true {
-            let bname: String = self.board_name.clone();
-            self.term.set_title(bname.as_str());
-        } else {
-            self.term.set_title(self.board_name.as_str());
-        }
+        self.term.set_title(self.board_name.clone().as_str());
 
         match self.input_state {
             InputMode::Command => {
-                self.put_bottom_bar("q - quit | s - save | c - create task | d - delete task | < or > - quick task block shift | Enter - Show Task/Hide Task".to_string());
+                // Only the keys for everyday work, the README lists every key
+                self.put_bottom_bar("q quit | s save | u/^r undo/redo | c/e/d new/edit/del task | </> shift | Enter show | A/R/D new/rename/del block | {/} reorder".to_string());
+                self.put_status();
                 self.calc_selector_pos();
-                if self.showing_task == true {
+                if self.showing_task {
                     self.show_task();
                 }
             }
-            InputMode::WritingTask => {
-                match self.writing_count {
-                    WritingTaskType::WritingTitle => {
-                        self.put_bottom_bar("Title:".to_string());
-                    }
-                    WritingTaskType::WritingDescription => {
-                        self.put_bottom_bar("Description:".to_string());
-                    }
+            InputMode::WritingTask | InputMode::EditingTask => match self.writing_count {
+                WritingTaskType::WritingTitle => {
+                    self.put_bottom_bar(format!(
+                        "Title | Priority: {}, Up/Down to change",
+                        self.tmp_task.priority.name()
+                    ));
+                    self.put_writing_line();
                 }
-
-                self.clear_writing_line();
-
-                self.term.put_str(
-                    self.writing_string.clone(),
-                    0,
-                    self.term.get_size().height - 2,
-                    Color::Black,
-                    Color::White,
+                WritingTaskType::WritingDue => {
+                    //A date that couldn't be read is reported in place of the hints
+                    let bar_str = self.status.clone().unwrap_or_else(|| {
+                        "Due: YYYY-MM-DD, today, tomorrow, mon..sun, +3d, +2w or +1m | empty for none"
+                            .to_string()
+                    });
+                    self.put_bottom_bar(bar_str);
+                    self.put_writing_line();
+                }
+                WritingTaskType::WritingTags => {
+                    self.put_bottom_bar(
+                        "Tags: separated by spaces or commas | empty for none".to_string(),
+                    );
+                    self.put_writing_line();
+                }
+                WritingTaskType::WritingAssignees => {
+                    let bar_str = self
+                        .status
+                        .clone()
+                        .unwrap_or_else(|| format!("Assignees from: {}", self.members.join(" ")));
+                    self.put_bottom_bar(bar_str);
+                    self.put_writing_line();
+                }
+                WritingTaskType::WritingDescription => {
+                    self.put_bottom_bar(
+                        "Description: Enter new line | Ctrl-s or Alt-Enter done | Esc cancel"
+                            .to_string(),
+                    );
+                    self.clear_writing_line();
+                    self.put_description_editor();
+                }
+            },
+            InputMode::WritingBoard => {
+                self.put_bottom_bar("Board Name:".to_string());
+                self.put_writing_line();
+            }
+            InputMode::WritingPath => {
+                self.put_bottom_bar("Save As:".to_string());
+                self.put_writing_line();
+            }
+            InputMode::WritingBlock => {
+                self.put_bottom_bar("New Block Name:".to_string());
+                self.put_writing_line();
+            }
+            InputMode::RenamingBlock => {
+                self.put_bottom_bar("Block Name:".to_string());
+                self.put_writing_line();
+            }
+            InputMode::WritingFilter => {
+                self.put_bottom_bar("Filter by tags: empty shows every task".to_string());
+                self.put_writing_line();
+            }
+            InputMode::WritingMembers => {
+                self.put_bottom_bar("Board Members: separated by spaces or commas".to_string());
+                self.put_writing_line();
+            }
+            InputMode::ViewingChecklist => {
+                self.put_bottom_bar("Checklist: Up/Down select | Space done | a add | e edit | d delete | Shift-Up/Down move | Esc close".to_string());
+                self.put_status();
+                self.show_task();
+            }
+            InputMode::WritingItem => {
+                self.put_bottom_bar("Checklist Item:".to_string());
+                self.show_task();
+                self.put_writing_line();
+            }
+            InputMode::ViewingLinks => {
+                self.put_bottom_bar(
+                    "Links: Up/Down select | Enter go to task | b edit blockers | Esc or g close"
+                        .to_string(),
                 );
+                self.put_status();
+                self.show_task();
             }
-            InputMode::WritingBoard => {
-                self.clear_writing_line();
-
-                self.term.put_str(
-                    self.writing_string.clone(),
-                    0,
-                    self.term.get_size().height - 2,
-                    Color::Black,
-                    Color::White,
+            InputMode::ViewingHistory => {
+                self.put_bottom_bar("History: Up/Down scroll | Esc or h close".to_string());
+                self.put_status();
+                self.show_task();
+            }
+            InputMode::ViewingStats => {
+                self.put_bottom_bar("Statistics: x export as CSV | Esc or T close".to_string());
+                self.put_status();
+                self.show_stats();
+            }
+            InputMode::WritingExport => {
+                self.put_bottom_bar("Export Statistics As:".to_string());
+                self.show_stats();
+                self.put_writing_line();
+            }
+            InputMode::ViewingFlow => {
+                self.put_bottom_bar(
+                    "Flow: Left/Right move | +/- zoom | r pick dates | Esc or F close".to_string(),
                 );
-
-                if self.writing_count == WritingTaskType::WritingTitle {
-                    self.put_bottom_bar("Board Name:".to_string());
-                }
+                self.put_status();
+                self.show_flow();
+            }
+            InputMode::WritingRange => {
+                let bar_str = self.status.clone().unwrap_or_else(|| {
+                    "Dates: first and last day, YYYY-MM-DD or today".to_string()
+                });
+                self.put_bottom_bar(bar_str);
+                self.show_flow();
+                self.put_writing_line();
+            }
+            InputMode::WritingBlockers => {
+                //A list that couldn't be used is reported in place of the hints
+                let bar_str = self.status.clone().unwrap_or_else(|| {
+                    "Blocked by: task numbers, as in #12 | empty for none".to_string()
+                });
+                self.put_bottom_bar(bar_str);
+                self.show_task();
+                self.put_writing_line();
+            }
+            InputMode::ConfirmingQuit => {
+                self.put_bottom_bar(
+                    "Unsaved changes! s save and quit | d discard and quit | c or Esc cancel"
+                        .to_string(),
+                );
+            }
+            InputMode::RestoringBackup => {
+                self.put_bottom_bar(
+                    "Restore Backup: Up/Down select | Enter restore | Esc cancel".to_string(),
+                );
+                self.show_backups();
+            }
+            InputMode::ViewingAgenda => {
+                self.put_bottom_bar(
+                    "Agenda: Up/Down select | Enter go to task | Esc or a close".to_string(),
+                );
+                self.show_agenda();
             }
         }
     }
 
     fn calc_selector_pos(&mut self) {
-        self.term.move_cursor(
-            self.block_list[self.selector.block_ptr as usize].position.x + 4,
-            self.block_list[self.selector.block_ptr as usize].position.y + 2,
+        let block_ptr = self.block_ptr();
+        let block = &self.block_list[block_ptr];
+        let row = self
+            .shown_position(block_ptr)
+            .unwrap_or(0)
+            .saturating_sub(block.scroll) as u16;
+        self.selector.position = Position {
+            x: block.position.x + 4,
+            y: block.position.y + 2 + 4 * row,
+        };
+        self.term
+            .move_cursor(self.selector.position.x, self.selector.position.y);
+    }
+
+    // Row of the key hints, the prompt and status line sits below it on the last row
+    fn bar_row(&self) -> u16 {
+        self.term.get_size().height.saturating_sub(2)
+    }
+
+    fn prompt_row(&self) -> u16 {
+        self.term.get_size().height.saturating_sub(1)
+    }
+
+    fn term_too_small(&self) -> bool {
+        self.term.get_size().width < MIN_WIDTH || self.term.get_size().height < MIN_HEIGHT
+    }
+
+    fn put_too_small(&mut self) {
+        let message = format!("Terminal too small, {}x{} needed", MIN_WIDTH, MIN_HEIGHT);
+        self.term.put_str(
+            message.clone(),
+            (self
+                .term
+                .get_size()
+                .width
+                .saturating_sub(message.len() as u16))
+                / 2,
+            self.term.get_size().height / 2,
+            Color::White,
+            Color::Reset,
         );
     }
 
     fn clear_writing_line(&mut self) {
-        self.term.move_cursor(0, self.term.get_size().height - 2);
-        self.term.clear_current_line();
+        self.term.clear_line(self.prompt_row());
     }
 
-    fn create_task(&mut self) {
-        match self.selector.block_ptr {
-            0 => {
-                self.tmp_task.status = TaskStatus::Todo;
+    fn put_status(&mut self) {
+        if let Some(status) = self.status.clone() {
+            self.clear_writing_line();
+            self.term.put_str(
+                self.trim_str(status, self.term.get_size().width as usize - 1),
+                0,
+                self.prompt_row(),
+                Color::White,
+                Color::DarkRed,
+            );
+        }
+    }
+
+    fn put_writing_line(&mut self) {
+        self.clear_writing_line();
+
+        let (shown, cursor_x) = self.line_editor.visible(self.term.get_size().width - 1);
+        self.term
+            .put_str(shown, 0, self.prompt_row(), Color::Black, Color::White);
+        self.term.move_cursor(cursor_x, self.prompt_row());
+    }
+
+    // Spreads the blocks evenly across the terminal width and down to the bottom bar. When
+    // they don't fit at their smallest width only some are shown, scrolled so the selected
+    // block stays on screen
+    fn layout_blocks(&mut self) {
+        let spacing: u16 = 2;
+        let margin: u16 = 2; // Room for the scroll arrows
+        let starting_y: u16 = 5;
+
+        let default_width: u16 = 30;
+        let min_width: u16 = 12;
+        let min_height: u16 = 5; // Room for a single task
+
+        let term_width = self.term.get_size().width;
+        let block_count = self.block_list.len();
+
+        let fit = ((term_width.saturating_sub(2 * margin) + spacing) / (min_width + spacing)).max(1)
+            as usize;
+        self.blocks_shown = block_count.min(fit);
+
+        let block_ptr = self.block_ptr();
+        if block_ptr < self.block_scroll {
+            self.block_scroll = block_ptr;
+        } else if block_ptr >= self.block_scroll + self.blocks_shown {
+            self.block_scroll = block_ptr + 1 - self.blocks_shown;
+        }
+        self.block_scroll = self
+            .block_scroll
+            .min(block_count.saturating_sub(self.blocks_shown));
+
+        let shown = self.blocks_shown as u16;
+        let available = term_width.saturating_sub(2 * margin + spacing * shown.saturating_sub(1));
+        let width = (available / shown.max(1)).clamp(min_width, default_width);
+        let total_width = width * shown + spacing * shown.saturating_sub(1);
+        let starting_x = (term_width.saturating_sub(total_width) / 2).max(margin);
+
+        let height = self
+            .bar_row()
+            .saturating_sub(starting_y + 1)
+            .max(min_height);
+
+        let block_scroll = self.block_scroll;
+        let shown_counts: Vec<usize> = (0..block_count)
+            .map(|i| self.shown_tasks(i).len())
+            .collect();
+        let task_pos = self.shown_position(block_ptr).unwrap_or(0);
+        for (i, block) in self.block_list.iter_mut().enumerate() {
+            let column = i.saturating_sub(block_scroll) as u16;
+            block.position = Position {
+                x: starting_x + (spacing + width) * column,
+                y: starting_y,
+            };
+            block.size.width = width;
+            block.size.height = height;
+
+            //Every block keeps its own scroll, the selected one follows the pointer. Scrolling
+            //counts the tasks on show, not the ones hidden by the filter
+            let rows = block.visible_tasks();
+            block.scroll = block.scroll.min(shown_counts[i].saturating_sub(rows));
+            if i == block_ptr {
+                if task_pos < block.scroll {
+                    block.scroll = task_pos;
+                } else if task_pos >= block.scroll + rows {
+                    block.scroll = task_pos + 1 - rows;
+                }
             }
-            1 => {
-                self.tmp_task.status = TaskStatus::Active;
+        }
+    }
+
+    fn block_shown(&self, block_index: usize) -> bool {
+        block_index >= self.block_scroll && block_index < self.block_scroll + self.blocks_shown
+    }
+
+    // A task is on show when it has one of the filter tags, or when there is no filter, and
+    // when it is assigned to the current user in the "my tasks" view
+    fn task_shown(&self, task: &Task) -> bool {
+        let tagged =
+            self.filter.is_empty() || task.tags.iter().any(|tag| self.filter.contains(tag));
+        let mine = !self.only_mine
+            || self
+                .user
+                .as_ref()
+                .is_some_and(|u| task.assignees.contains(u));
+        tagged && mine
+    }
+
+    // Indices of the tasks of a block that are on show
+    fn shown_tasks(&self, block_index: usize) -> Vec<usize> {
+        self.block_list[block_index]
+            .task_list
+            .iter()
+            .enumerate()
+            .filter(|(_, task)| self.task_shown(task))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    // Place of the selected task among the tasks on show in its block
+    fn shown_position(&self, block_index: usize) -> Option<usize> {
+        let task_ptr = self.selected_task()?;
+        self.shown_tasks(block_index)
+            .iter()
+            .position(|i| *i == task_ptr)
+    }
+
+    // Index of the block under the pointer
+    fn block_ptr(&self) -> usize {
+        self.find_block(self.selector.block_id).unwrap_or(0)
+    }
+
+    // Index of the task under the pointer in its block, 0 when it points at none
+    fn task_ptr(&self) -> usize {
+        self.selector
+            .task_id
+            .and_then(|id| {
+                self.block_list[self.block_ptr()]
+                    .task_list
+                    .iter()
+                    .position(|task| task.id == id)
+            })
+            .unwrap_or(0)
+    }
+
+    // Points at the block and task at the given places, or at no task past the end of the block
+    fn point_at(&mut self, block_ptr: usize, task_ptr: usize) {
+        let block = &self.block_list[block_ptr];
+        self.selector.block_id = block.id;
+        self.selector.task_id = block.task_list.get(task_ptr).map(|task| task.id);
+    }
+
+    // Index of the task under the pointer, None when it points at no task on show
+    fn selected_task(&self) -> Option<usize> {
+        let id = self.selector.task_id?;
+        self.block_list[self.block_ptr()]
+            .task_list
+            .iter()
+            .position(|task| task.id == id)
+            .filter(|task_ptr| {
+                self.task_shown(&self.block_list[self.block_ptr()].task_list[*task_ptr])
+            })
+    }
+
+    // Moves the pointer off a task the filter hides or that is gone, and onto the first task
+    // of a block it entered or that just got one
+    fn fix_selector(&mut self) {
+        if self.selected_task().is_none() {
+            let block_ptr = self.block_ptr();
+            let shown = self.shown_tasks(block_ptr);
+            self.point_at(block_ptr, shown.first().copied().unwrap_or(0));
+        }
+    }
+
+    // Moves the pointer to the next or previous task on show in the block
+    fn step_selector(&mut self, down: bool) {
+        let shown = self.shown_tasks(self.block_ptr());
+        let target = match self.shown_position(self.block_ptr()) {
+            Some(pos) if down => pos + 1,
+            Some(pos) if pos > 0 => pos - 1,
+            _ => return,
+        };
+        if let Some(task_ptr) = shown.get(target) {
+            self.point_at(self.block_ptr(), *task_ptr);
+        }
+    }
+
+    // Chip colour of a tag, from the config or else picked from its name so it stays the same
+    fn tag_color(&self, tag: &str) -> Color {
+        match self.config.tag_colors.get(tag) {
+            Some(color) => *color,
+            None => Block::color(tag.bytes().map(usize::from).sum()),
+        }
+    }
+
+    fn status_for_block(&self, block_index: usize) -> TaskStatus {
+        TaskStatus::for_block(block_index, self.block_list.len())
+    }
+
+    fn refresh_task_status(&mut self) {
+        for i in 0..self.block_list.len() {
+            let status = self.status_for_block(i);
+            for task in self.block_list[i].task_list.iter_mut() {
+                task.status = status.clone();
             }
-            2 => self.tmp_task.status = TaskStatus::Completed,
-            _ => {}
         }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let (block_id, task_id) = self.selected_ids();
+        Snapshot {
+            board_name: self.board_name.clone(),
+            members: self.members.clone(),
+            block_list: self.block_list.clone(),
+            block_id,
+            task_id,
+            revision: self.revision,
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.board_name = snapshot.board_name;
+        self.members = snapshot.members;
+        self.block_list = snapshot.block_list;
+        self.select(snapshot.block_id, snapshot.task_id);
+        self.revision = snapshot.revision;
+        self.is_modified = self.saved_revision != self.revision;
+        self.last_change = Instant::now();
+        self.autosave_failed = false;
+    }
+
+    // Records the current state in the history, to be called right before every mutation
+    fn checkpoint(&mut self) {
+        self.history.record(self.snapshot());
+        self.last_revision += 1;
+        self.revision = self.last_revision;
+        self.is_modified = true;
+        self.last_change = Instant::now();
+        self.autosave_failed = false;
+    }
+
+    fn undo(&mut self) {
+        if let Some(snapshot) = self.history.undo(self.snapshot()) {
+            self.restore(snapshot);
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(snapshot) = self.history.redo(self.snapshot()) {
+            self.restore(snapshot);
+        }
+    }
+
+    // IDs are handed out in order and never given twice, not even after an undo
+    fn new_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id - 1
+    }
 
-        self.block_list[self.selector.block_ptr as usize]
+    fn create_task(&mut self) {
+        let block_ptr = self.block_ptr();
+        let now = Local::now();
+        self.tmp_task.status = self.status_for_block(block_ptr);
+        self.tmp_task.id = self.new_id();
+        self.tmp_task.created = Some(now);
+        self.tmp_task.updated = Some(now);
+        self.tmp_task.history = vec![TaskEvent::Created {
+            at: now,
+            block: self.block_list[block_ptr].id,
+        }];
+
+        self.checkpoint();
+        self.block_list[block_ptr]
             .task_list
             .push(self.tmp_task.clone());
-        self.is_modified = true;
-        self.term.clear_screen();
     }
 
-    fn remove_task(&mut self) -> Option<Task> {
-        let mut r = None;
-        if self.block_list[self.selector.block_ptr as usize]
+    // Replaces the selected task with the edited copy, keeping its place in the block. An
+    // edit that changes nothing leaves the task and the board untouched
+    fn edit_task(&mut self) {
+        let block_ptr = self.block_ptr();
+        let task_ptr = self.task_ptr();
+        let changed = self.block_list[block_ptr]
             .task_list
-            .len()
-            != 0
+            .get(task_ptr)
+            .is_some_and(|task| !task.same_details(&self.tmp_task));
+        if changed {
+            self.checkpoint();
+            self.tmp_task.record(TaskEvent::Edited { at: Local::now() });
+            self.block_list[block_ptr].task_list[task_ptr] = self.tmp_task.clone();
+        }
+    }
+
+    fn change_priority(&mut self, raise: bool) {
+        let block_ptr = self.block_ptr();
+        let task_ptr = self.task_ptr();
+        if let Some(task) = self
+            .selected_task()
+            .map(|_| &self.block_list[block_ptr].task_list[task_ptr])
         {
-            r = Some(
-                self.block_list[self.selector.block_ptr as usize]
-                    .task_list
-                    .remove(self.selector.task_ptr.into()),
-            );
+            let priority = if raise {
+                task.priority.raised()
+            } else {
+                task.priority.lowered()
+            };
+            if priority != task.priority {
+                self.checkpoint();
+                let task = &mut self.block_list[block_ptr].task_list[task_ptr];
+                task.record(TaskEvent::Prioritized {
+                    at: Local::now(),
+                    from: task.priority,
+                    to: priority,
+                });
+                task.priority = priority;
+            }
+        }
+    }
+
+    // Puts the most pressing tasks at the top of the selected block, keeping the order of
+    // tasks with the same priority. The pointer follows its task by ID
+    fn sort_block(&mut self) {
+        let block_ptr = self.block_ptr();
+        let task_list = &self.block_list[block_ptr].task_list;
+
+        let mut order: Vec<usize> = (0..task_list.len()).collect();
+        order.sort_by_key(|i| std::cmp::Reverse(task_list[*i].priority));
+        if order.iter().enumerate().all(|(pos, i)| pos == *i) {
+            return;
+        }
+
+        let sorted = order.iter().map(|i| task_list[*i].clone()).collect();
+
+        self.checkpoint();
+        self.block_list[block_ptr].task_list = sorted;
+    }
+
+    fn remove_task(&mut self) -> Option<Task> {
+        let block_ptr = self.block_ptr();
+        let task_ptr = self.task_ptr();
+        let mut r = None;
+        if !self.block_list[block_ptr].task_list.is_empty() {
+            r = Some(self.block_list[block_ptr].task_list.remove(task_ptr));
         }
         r
     }
 
+    fn delete_task(&mut self) {
+        if let Some(task_ptr) = self.selected_task() {
+            self.checkpoint();
+            if let Some(task) = self.remove_task() {
+                //Nothing waits on a task that is gone
+                for block in self.block_list.iter_mut() {
+                    for other in block.task_list.iter_mut() {
+                        other.blocked_by.retain(|id| *id != task.id);
+                    }
+                }
+            }
+            self.point_at(self.block_ptr(), task_ptr.saturating_sub(1));
+        }
+    }
+
+    fn shift_task(&mut self, target_block: usize) {
+        if let Some(task_ptr) = self.selected_task() {
+            self.checkpoint();
+            if let Some(mut t) = self.remove_task() {
+                t.status = self.status_for_block(target_block);
+                t.record(TaskEvent::Moved {
+                    at: Local::now(),
+                    from: self.block_list[self.block_ptr()].id,
+                    to: self.block_list[target_block].id,
+                });
+                self.block_list[target_block].task_list.push(t);
+                self.point_at(self.block_ptr(), task_ptr.saturating_sub(1));
+            }
+        }
+    }
+
+    fn create_block(&mut self, title: String) {
+        let index = self.block_ptr() + 1;
+        self.checkpoint();
+        let id = self.new_id();
+        self.block_list.insert(index, Block::new(id, title));
+        self.point_at(index, 0);
+
+        self.refresh_task_status();
+    }
+
+    fn rename_board(&mut self, board_name: String) {
+        self.checkpoint();
+        self.board_name = board_name;
+    }
+
+    fn rename_block(&mut self, title: String) {
+        self.checkpoint();
+        let block_ptr = self.block_ptr();
+        self.block_list[block_ptr].title = title;
+    }
+
+    // Only empty blocks can be removed, and the board always keeps at least one
+    // Only empty blocks can go and the board keeps at least one, otherwise the reason is shown
+    fn remove_block(&mut self) {
+        let index = self.block_ptr();
+        if self.block_list.len() == 1 {
+            self.status = Some("The board needs at least one block".to_string());
+        } else if !self.block_list[index].task_list.is_empty() {
+            self.status = Some(format!(
+                "{} still has tasks, move or delete them first",
+                self.block_list[index].title
+            ));
+        } else {
+            self.checkpoint();
+            self.block_list.remove(index);
+            self.point_at(index.min(self.block_list.len() - 1), 0);
+
+            self.refresh_task_status();
+        }
+    }
+
+    fn move_block(&mut self, target_block: usize) {
+        let index = self.block_ptr();
+        if target_block < self.block_list.len() {
+            self.checkpoint();
+            self.block_list.swap(index, target_block);
+
+            self.refresh_task_status();
+        }
+    }
+
+    // Placement of a popup as x, y, width and height. It is centered above the bottom bar and
+    // shrinks to fit small terminals
+    fn popup(&self, width: u16, height: u16) -> (u16, u16, u16, u16) {
+        let boxw = width
+            .min(self.term.get_size().width.saturating_sub(4))
+            .max(10);
+        let boxh = height.min(self.bar_row().saturating_sub(3)).max(5);
+        let boxx = (self.term.get_size().width.saturating_sub(boxw + 1)) / 2;
+        let boxy = (self.bar_row().saturating_sub(boxh + 1) / 2).max(1);
+
+        (boxx, boxy, boxw, boxh)
+    }
+
+    fn task_popup(&self) -> (u16, u16, u16, u16) {
+        self.popup(50, 20)
+    }
+
     fn show_task(&mut self) {
-        if self.block_list[self.selector.block_ptr as usize]
-            .task_list
-            .len()
-            > 0
-        {
-            let boxx: u16 = 30;
-            let boxy: u16 = 6;
-            let boxw: u16 = 50;
-            let boxh: u16 = 20;
+        if self.selected_task().is_some() {
+            let (boxx, boxy, boxw, boxh) = self.task_popup();
 
             self.term
                 .draw_rect(boxx, boxy, boxw, boxh, Color::White, Some(Color::White));
 
-            //Title of the entry being displayed
+            //Number and title of the entry being displayed
+            let task = &self.block_list[self.block_ptr()].task_list[self.task_ptr()];
+            let id_str = format!("#{} ", task.id);
+            let title = task.title.clone();
             self.term.put_str(
-                self.block_list[self.selector.block_ptr as usize].task_list
-                    [self.selector.task_ptr as usize]
-                    .title
-                    .clone(),
+                id_str.clone(),
                 boxx + 1,
                 boxy,
+                Color::DarkGrey,
+                Color::White,
+            );
+            self.term.put_str(
+                title,
+                boxx + 1 + id_str.len() as u16,
+                boxy,
+                Color::Black,
+                Color::White,
+            );
+
+            let priority = self.block_list[self.block_ptr()].task_list[self.task_ptr()].priority;
+            let priority_x = boxx + boxw - priority.name().len() as u16;
+            self.term.put_str(
+                "\u{25CF}".to_string(),
+                priority_x - 2,
+                boxy,
+                priority.color(),
+                Color::Black,
+            );
+            self.term.put_str(
+                priority.name().to_string(),
+                priority_x,
+                boxy,
                 Color::Black,
                 Color::White,
             );
 
-            let mut broken_description: Vec<String> = vec![];
-            let mut tmp_str = String::from("");
-            let tmp_description = self.block_list[self.selector.block_ptr as usize].task_list
-                [self.selector.task_ptr as usize]
-                .description
+            let task = &self.block_list[self.block_ptr()].task_list[self.task_ptr()];
+            if let Some(due) = task.due {
+                self.term.put_str(
+                    format!("Due {}", due.format("%a %Y-%m-%d")),
+                    boxx + 1,
+                    boxy + boxh,
+                    urgency(task, Local::now().date_naive()).color(true),
+                    Color::White,
+                );
+            }
+            if !task.assignees.is_empty() {
+                let assignees_str = self.trim_str(
+                    task.assignees.join(", "),
+                    (boxw / 2).saturating_sub(2).into(),
+                );
+                self.term.put_str(
+                    assignees_str.clone(),
+                    boxx + boxw - assignees_str.width() as u16,
+                    boxy + boxh,
+                    Color::Black,
+                    Color::White,
+                );
+            }
+
+            let broken_description = wrap_text(
+                &self.block_list[self.block_ptr()].task_list[self.task_ptr()].description,
+                (boxw - 3).into(),
+            );
+
+            //The checklist takes up to half of the popup, under the description
+            let checklist = self.block_list[self.block_ptr()].task_list[self.task_ptr()]
+                .checklist
+                .clone();
+            let editing_checklist = matches!(
+                self.input_state,
+                InputMode::ViewingChecklist | InputMode::WritingItem
+            );
+            let mut rows = (boxh - 3) as usize;
+            if !checklist.is_empty() || editing_checklist {
+                let list_rows = checklist.len().clamp(1, rows / 2);
+                rows -= list_rows + 2;
+                self.put_checklist(
+                    &checklist,
+                    boxy + 3 + rows as u16,
+                    list_rows,
+                    editing_checklist,
+                );
+            }
+
+            //The latest events go above the checklist, the whole history takes up to half of
+            //what is left while it is being scrolled
+            let history = self.block_list[self.block_ptr()].task_list[self.task_ptr()]
+                .history
                 .clone();
+            let browsing_history = self.input_state == InputMode::ViewingHistory;
+            if browsing_history && rows >= 3 {
+                let list_rows = history.len().clamp(1, rows / 2);
+                rows -= list_rows + 2;
+                self.put_history(&history, boxy + 3 + rows as u16, list_rows, true);
+            } else if !history.is_empty() && rows >= 8 {
+                let list_rows = history.len().min(HISTORY_ROWS).min(rows / 3);
+                rows -= list_rows + 2;
+                self.put_history(&history, boxy + 3 + rows as u16, list_rows, false);
+            }
 
-            for c in 0..tmp_description.len() {
-                tmp_str.push(tmp_description.chars().nth(c).unwrap());
-                if tmp_str.len() as u16 == boxw - 3 || c + 1 >= tmp_description.len() {
-                    broken_description.push(tmp_str.clone());
-                    tmp_str = "".to_string();
-                }
+            //Blockers and blocked tasks go above the history
+            let links = self.links();
+            let editing_links = matches!(
+                self.input_state,
+                InputMode::ViewingLinks | InputMode::WritingBlockers
+            );
+            if (!links.is_empty() || editing_links) && rows >= 4 {
+                let list_rows = links.len().clamp(1, rows / 2);
+                rows -= list_rows + 2;
+                self.put_links(&links, boxy + 3 + rows as u16, list_rows, editing_links);
             }
 
-            for line in 0..broken_description.len() {
+            //Keep the last page of a long description in view
+            self.task_scroll = self
+                .task_scroll
+                .min(broken_description.len().saturating_sub(rows));
+
+            for (line, line_str) in broken_description
+                .iter()
+                .skip(self.task_scroll)
+                .take(rows)
+                .enumerate()
+            {
                 self.term.put_str(
-                    broken_description[line].to_string(),
+                    line_str.to_string(),
                     boxx + 2,
                     boxy + 2 + (line as u16),
                     Color::Black,
                     Color::White,
                 )
             }
+
+            //Scroll indicators
+            if self.task_scroll > 0 {
+                self.term.put_str(
+                    "\u{25B2}".to_string(),
+                    boxx + boxw - 1,
+                    boxy + 2,
+                    Color::Black,
+                    Color::White,
+                );
+            }
+            if self.task_scroll + rows < broken_description.len() {
+                self.term.put_str(
+                    "\u{25BC}".to_string(),
+                    boxx + boxw - 1,
+                    boxy + 1 + rows as u16,
+                    Color::Black,
+                    Color::White,
+                );
+            }
         }
     }
 
-    pub fn process_input(&mut self) {
-        if self.input_state == InputMode::Command {
-            if let Event::Key(key) = read().unwrap() {
-                match key {
-                    // QUIT
-                    KeyEvent {
-                        code: KeyCode::Char('q'),
-                        modifiers: KeyModifiers::NONE,
-                        kind: KeyEventKind::Press,
-                        ..
-                    } => {
-                        if self.input_state == InputMode::Command {
-                            self.quit = true;
-                        }
-                    }
+    // Draws the checklist heading at row `y` and `rows` items under it, scrolled to keep the
+    // selected item in view while the checklist is being edited
+    fn put_checklist(&mut self, checklist: &[ChecklistItem], y: u16, rows: usize, editing: bool) {
+        let (boxx, _, boxw, _) = self.task_popup();
+        self.checklist_ptr = self.checklist_ptr.min(checklist.len().saturating_sub(1));
+
+        let done = checklist.iter().filter(|item| item.done).count();
+        self.term.put_str(
+            format!("Checklist {}/{}", done, checklist.len()),
+            boxx + 2,
+            y,
+            Color::Black,
+            Color::White,
+        );
+        if checklist.is_empty() {
+            self.term.put_str(
+                "No items, a adds one".to_string(),
+                boxx + 2,
+                y + 1,
+                Color::DarkGrey,
+                Color::White,
+            );
+            return;
+        }
+
+        let first = if editing {
+            (self.checklist_ptr + 1).saturating_sub(rows)
+        } else {
+            0
+        };
+        for (row, (i, item)) in checklist
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(rows)
+            .enumerate()
+        {
+            let item_y = y + 1 + row as u16;
+            let selected = editing && i == self.checklist_ptr;
+            let (box_fg, text_fg, bg) = match (selected, item.done) {
+                (true, _) => (Color::White, Color::White, Color::Black),
+                (false, true) => (Color::DarkGreen, Color::DarkGrey, Color::White),
+                (false, false) => (Color::Black, Color::Black, Color::White),
+            };
+            if selected {
+                self.term
+                    .put_str(" ".repeat((boxw - 1).into()), boxx + 1, item_y, bg, bg);
+                self.term.move_cursor(boxx + 3, item_y);
+            }
+            let check = if item.done { "[x]" } else { "[ ]" };
+            self.term
+                .put_str(check.to_string(), boxx + 2, item_y, box_fg, bg);
+            self.term.put_str(
+                self.trim_str(item.text.clone(), (boxw - 7).into()),
+                boxx + 6,
+                item_y,
+                text_fg,
+                bg,
+            );
+        }
+
+        //Scroll indicators
+        if first > 0 {
+            self.term.put_str(
+                "\u{25B2}".to_string(),
+                boxx + boxw - 1,
+                y + 1,
+                Color::Black,
+                Color::White,
+            );
+        }
+        if first + rows < checklist.len() {
+            self.term.put_str(
+                "\u{25BC}".to_string(),
+                boxx + boxw - 1,
+                y + rows as u16,
+                Color::Black,
+                Color::White,
+            );
+        }
+    }
+
+    // Draws the links heading at row `y` and `rows` linked tasks under it, scrolled to keep
+    // the selected one in view while the links are being browsed
+    fn put_links(&mut self, links: &[(u64, bool)], y: u16, rows: usize, browsing: bool) {
+        let (boxx, _, boxw, _) = self.task_popup();
+        self.links_ptr = self.links_ptr.min(links.len().saturating_sub(1));
+
+        self.term
+            .put_str("Links".to_string(), boxx + 2, y, Color::Black, Color::White);
+        if links.is_empty() {
+            self.term.put_str(
+                "None, b sets what blocks this task".to_string(),
+                boxx + 2,
+                y + 1,
+                Color::DarkGrey,
+                Color::White,
+            );
+            return;
+        }
+
+        let first = if browsing {
+            (self.links_ptr + 1).saturating_sub(rows)
+        } else {
+            0
+        };
+        for (row, (i, (id, blocker))) in links.iter().enumerate().skip(first).take(rows).enumerate()
+        {
+            let (b, t) = match self.find_task(*id) {
+                Some(found) => found,
+                None => continue,
+            };
+            let task = &self.block_list[b].task_list[t];
+            let done = matches!(task.status, TaskStatus::Completed);
+            let line = format!(
+                "{} #{} {}{}",
+                if *blocker { "blocked by" } else { "blocks    " },
+                id,
+                task.title,
+                if done { " (done)" } else { "" }
+            );
+
+            let item_y = y + 1 + row as u16;
+            let (fg, bg) = if browsing && i == self.links_ptr {
+                self.term.put_str(
+                    " ".repeat((boxw - 1).into()),
+                    boxx + 1,
+                    item_y,
+                    Color::Black,
+                    Color::Black,
+                );
+                self.term.move_cursor(boxx + 2, item_y);
+                (Color::White, Color::Black)
+            } else if *blocker && !done {
+                (Color::DarkRed, Color::White)
+            } else {
+                (Color::Black, Color::White)
+            };
+            self.term.put_str(
+                self.trim_str(line, (boxw - 3).into()),
+                boxx + 2,
+                item_y,
+                fg,
+                bg,
+            );
+        }
+
+        //Scroll indicators
+        if first > 0 {
+            self.term.put_str(
+                "\u{25B2}".to_string(),
+                boxx + boxw - 1,
+                y + 1,
+                Color::Black,
+                Color::White,
+            );
+        }
+        if first + rows < links.len() {
+            self.term.put_str(
+                "\u{25BC}".to_string(),
+                boxx + boxw - 1,
+                y + rows as u16,
+                Color::Black,
+                Color::White,
+            );
+        }
+    }
+
+    // Draws the history heading at row `y` and `rows` events of the task under it, the latest
+    // ones unless the history is being scrolled back
+    fn put_history(&mut self, history: &[TaskEvent], y: u16, rows: usize, browsing: bool) {
+        let (boxx, _, boxw, _) = self.task_popup();
+        let rows = rows.min(history.len());
+        if browsing {
+            self.history_scroll = self.history_scroll.min(history.len() - rows);
+        } else {
+            self.history_scroll = 0;
+        }
+        let end = history.len() - self.history_scroll;
+        let task = &self.block_list[self.block_ptr()].task_list[self.task_ptr()];
+
+        let heading = if self.history_scroll > 0 {
+            format!("History, {}-{} of {}", end - rows + 1, end, history.len())
+        } else if history.len() > rows {
+            format!("History, last {} of {}", rows, history.len())
+        } else {
+            "History".to_string()
+        };
+        let heading_len = heading.len();
+        self.term
+            .put_str(heading, boxx + 2, y, Color::Black, Color::White);
+        //The time of the last change only goes next to the heading when there is room for both
+        let updated_str = task
+            .updated
+            .map(|updated| format!("updated {}", updated.format(TIME_FORMAT)))
+            .filter(|updated_str| heading_len + updated_str.len() + 4 <= boxw as usize);
+        if let Some(updated_str) = updated_str {
+            self.term.put_str(
+                updated_str.clone(),
+                boxx + boxw - 1 - updated_str.len() as u16,
+                y,
+                Color::DarkGrey,
+                Color::White,
+            );
+        }
+
+        if history.is_empty() {
+            self.term.put_str(
+                "No events recorded".to_string(),
+                boxx + 2,
+                y + 1,
+                Color::DarkGrey,
+                Color::White,
+            );
+            return;
+        }
+
+        let lines: Vec<String> = history[end - rows..end]
+            .iter()
+            .map(|event| {
+                let what = match event {
+                    TaskEvent::Created { block, .. } => {
+                        format!("created in {}", self.block_title(*block))
+                    }
+                    TaskEvent::Moved { from, to, .. } => format!(
+                        "moved {} \u{2192} {}",
+                        self.block_title(*from),
+                        self.block_title(*to)
+                    ),
+                    TaskEvent::Edited { .. } => "edited".to_string(),
+                    TaskEvent::Prioritized { from, to, .. } => {
+                        format!("priority {} \u{2192} {}", from.name(), to.name())
+                    }
+                    TaskEvent::ChecklistChanged { done, total, .. } => {
+                        format!("checklist {}/{}", done, total)
+                    }
+                    TaskEvent::BlockersChanged { blocked_by, .. } if blocked_by.is_empty() => {
+                        "no longer blocked".to_string()
+                    }
+                    TaskEvent::BlockersChanged { blocked_by, .. } => {
+                        let ids: Vec<String> =
+                            blocked_by.iter().map(|id| format!("#{}", id)).collect();
+                        format!("blocked by {}", ids.join(" "))
+                    }
+                };
+                format!("{}  {}", event.at().format(TIME_FORMAT), what)
+            })
+            .collect();
+        for (row, line) in lines.into_iter().enumerate() {
+            self.term.put_str(
+                self.trim_str(line, (boxw - 3).into()),
+                boxx + 2,
+                y + 1 + row as u16,
+                Color::Black,
+                Color::White,
+            );
+        }
+
+        //Scroll indicators
+        if end > rows {
+            self.term.put_str(
+                "\u{25B2}".to_string(),
+                boxx + boxw - 1,
+                y + 1,
+                Color::Black,
+                Color::White,
+            );
+        }
+        if self.history_scroll > 0 {
+            self.term.put_str(
+                "\u{25BC}".to_string(),
+                boxx + boxw - 1,
+                y + rows as u16,
+                Color::Black,
+                Color::White,
+            );
+        }
+    }
+
+    // Title of the block with the given ID, which may have been deleted since
+    fn block_title(&self, id: u64) -> String {
+        match self.find_block(id) {
+            Some(b) => self.block_list[b].title.clone(),
+            None => "a deleted block".to_string(),
+        }
+    }
+
+    fn put_description_editor(&mut self) {
+        let (boxx, boxy, boxw, boxh) = self.task_popup();
+
+        self.term
+            .draw_rect(boxx, boxy, boxw, boxh, Color::White, Some(Color::White));
+
+        self.term.put_str(
+            self.tmp_task.title.clone(),
+            boxx + 1,
+            boxy,
+            Color::Black,
+            Color::White,
+        );
+
+        let (lines, cursor_x, cursor_y) = self.text_editor.view(boxw - 3, boxh - 3);
+        for (line, line_str) in lines.into_iter().enumerate() {
+            self.term.put_str(
+                line_str,
+                boxx + 2,
+                boxy + 2 + (line as u16),
+                Color::Black,
+                Color::White,
+            )
+        }
+
+        self.term
+            .move_cursor(boxx + 2 + cursor_x, boxy + 2 + cursor_y);
+    }
+
+    // Waits for the next terminal event, gives up when a termination signal arrives
+    fn next_event(&mut self) -> Option<Event> {
+        loop {
+            if self.terminate.load(Ordering::Relaxed) {
+                self.terminate_quit();
+                return None;
+            }
+            // Returning without an event redraws the board with the new save time
+            if let Some(interval) = self.config.autosave {
+                if self.last_change.elapsed() >= Duration::from_secs(interval) && self.autosave() {
+                    return None;
+                }
+            }
+            // A terminal that is gone, as after a hangup, is handled like a termination signal.
+            // Events crossterm has already read go first, then it waits for new input
+            if !self.term.wait_for_input(Duration::ZERO) {
+                self.terminate_quit();
+                return None;
+            }
+            match poll(Duration::ZERO).and_then(|ready| ready.then(read).transpose()) {
+                Ok(Some(event)) => return Some(event),
+                Ok(None) => {}
+                Err(_) => {
+                    self.terminate_quit();
+                    return None;
+                }
+            }
+            self.term.wait_for_input(Duration::from_millis(250));
+        }
+    }
+
+    // Saves a modified board that already has a file. Returns whether anything was attempted
+    fn autosave(&mut self) -> bool {
+        if !self.is_modified
+            || self.autosave_failed
+            || self.file_path.is_none()
+            || self.config.autosave.is_none()
+        {
+            return false;
+        }
+
+        if let Err(e) = self.save() {
+            self.autosave_failed = true;
+            self.status = Some(format!(
+                "Autosave to {} failed: {}",
+                self.file_path_str(),
+                e
+            ));
+        }
+        true
+    }
+
+    // Quits straight away, or asks what to do first when there are unsaved changes
+    fn request_quit(&mut self) {
+        if self.is_modified {
+            self.input_state = InputMode::ConfirmingQuit;
+        } else {
+            self.quit = true;
+        }
+    }
+
+    // `board.json.recovery.json`, or `<board name>.recovery.json` for a board without a file
+    fn recovery_path(&self) -> PathBuf {
+        let mut path = match &self.file_path {
+            Some(p) => p.clone().into_os_string(),
+            None => self.board_name.clone().into(),
+        };
+        path.push(".recovery.json");
+        PathBuf::from(path)
+    }
+
+    // There is no one left to ask, so unsaved changes go to a recovery file next to the board
+    fn terminate_quit(&mut self) {
+        if self.is_modified {
+            let _ = self.write_board(&self.recovery_path(), 0);
+        }
+        self.quit = true;
+    }
+
+    pub fn process_input(&mut self) {
+        let event = match self.next_event() {
+            Some(event) => event,
+            None => return,
+        };
+
+        // Focus and size changes are not input, so they leave the status message alone
+        match event {
+            Event::FocusLost => {
+                self.autosave();
+                return;
+            }
+            Event::FocusGained => return,
+            Event::Resize(width, height) => {
+                self.term.resize(width, height);
+                return;
+            }
+            _ => {}
+        }
+
+        if self.status.take().is_some() {
+            self.clear_writing_line();
+        }
+
+        // Ctrl-C quits from anywhere, but goes through the same confirmation as q
+        if let Event::Key(KeyEvent {
+            code: KeyCode::Char('c'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event
+        {
+            if self.input_state != InputMode::ConfirmingQuit {
+                if self.input_state != InputMode::Command {
+                    self.cancel_prompt();
+                }
+                self.request_quit();
+                return;
+            }
+        }
+
+        if self.input_state == InputMode::Command {
+            if let Event::Key(key) = event {
+                match key {
+                    // QUIT
+                    KeyEvent {
+                        code: KeyCode::Char('q'),
+                        modifiers: KeyModifiers::NONE,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.request_quit();
+                    }
 
                     // SAVE
                     KeyEvent {
@@ -342,428 +1543,1648 @@ impl Board {
                         kind: KeyEventKind::Press,
                         ..
                     } => {
-                        if self.check_save() == true {
-                            self.save();
-                            self.is_modified = false;
+                        if self.file_path.is_some() {
+                            self.save_or_report();
                         } else {
-                            self.input_state = InputMode::WritingBoard;
+                            self.open_save_as();
                         }
-                        self.term.clear_screen();
                     }
 
-                    // CREATE TASK
+                    // SAVE AS
                     KeyEvent {
-                        code: KeyCode::Char('c'),
+                        code: KeyCode::Char('S'),
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.open_save_as();
+                    }
+
+                    // RENAME BOARD
+                    KeyEvent {
+                        code: KeyCode::Char('N'),
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        let board_name = self.board_name.clone();
+                        self.open_prompt(
+                            InputMode::WritingBoard,
+                            WritingTaskType::WritingTitle,
+                            &board_name,
+                        );
+                    }
+
+                    // RESTORE BACKUP
+                    KeyEvent {
+                        code: KeyCode::Char('B'),
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.open_backups();
+                    }
+
+                    // AGENDA
+                    KeyEvent {
+                        code: KeyCode::Char('a'),
                         modifiers: KeyModifiers::NONE,
                         kind: KeyEventKind::Press,
                         ..
                     } => {
-                        if self.input_state == InputMode::Command {
-                            self.input_state = InputMode::WritingTask;
-                        }
+                        self.open_agenda();
                     }
 
-                    // DELETE TASK
+                    // FILTER
                     KeyEvent {
-                        code: KeyCode::Char('d'),
+                        code: KeyCode::Char('f'),
                         modifiers: KeyModifiers::NONE,
                         kind: KeyEventKind::Press,
                         ..
                     } => {
-                        if self.input_state == InputMode::Command {
-                            self.remove_task();
-                            self.is_modified = true;
-                            self.term.clear_screen();
-                        }
+                        let filter = self.filter.join(" ");
+                        self.open_prompt(
+                            InputMode::WritingFilter,
+                            WritingTaskType::WritingTitle,
+                            &filter,
+                        );
                     }
 
-                    // SEE TASK
+                    // MY TASKS
                     KeyEvent {
-                        code: KeyCode::Enter,
+                        code: KeyCode::Char('m'),
                         modifiers: KeyModifiers::NONE,
                         kind: KeyEventKind::Press,
                         ..
                     } => {
-                        if self.input_state == InputMode::Command {
-                            if self.showing_task == true {
-                                self.showing_task = false;
-                                self.term.clear_screen();
-                            } else {
-                                self.showing_task = true;
-                                self.term.clear_screen();
-                            }
+                        if self.user.is_some() {
+                            self.only_mine = !self.only_mine;
+                            self.fix_selector();
+                        } else {
+                            self.status = Some(
+                                "Set \"user\" in the config file or $USER to see your tasks"
+                                    .to_string(),
+                            );
                         }
                     }
 
-                    // MOVE SELECTOR UP
+                    // MEMBERS
                     KeyEvent {
-                        code: KeyCode::Up,
+                        code: KeyCode::Char('M'),
                         kind: KeyEventKind::Press,
                         ..
                     } => {
-                        if self.input_state == InputMode::Command {
-                            if self.selector.task_ptr > 0 {
-                                // Can go up Minus
-                                self.selector.task_ptr -= 1;
-                                self.term.clear_screen();
-                            }
-                        }
+                        let members = self.members.join(" ");
+                        self.open_prompt(
+                            InputMode::WritingMembers,
+                            WritingTaskType::WritingTitle,
+                            &members,
+                        );
                     }
 
-                    // MOVE SELECTOR DOWN
+                    // STATISTICS
                     KeyEvent {
-                        code: KeyCode::Down,
+                        code: KeyCode::Char('T'),
                         kind: KeyEventKind::Press,
                         ..
                     } => {
-                        if self.input_state == InputMode::Command {
-                            if self.block_list[self.selector.block_ptr as usize]
-                                .task_list
-                                .len()
-                                != 0
-                            {
-                                if (self.selector.task_ptr as usize)
-                                    < self.block_list[self.selector.block_ptr as usize]
-                                        .task_list
-                                        .len()
-                                        - 1
-                                {
-                                    //Can go down - Plus
-                                    self.selector.task_ptr += 1;
-                                    self.term.clear_screen();
-                                }
-                            }
-                        }
+                        self.input_state = InputMode::ViewingStats;
                     }
 
-                    // MOVE SELECTOR RIGHT
+                    // CUMULATIVE FLOW
                     KeyEvent {
-                        code: KeyCode::Right,
+                        code: KeyCode::Char('F'),
                         kind: KeyEventKind::Press,
                         ..
                     } => {
-                        if self.input_state == InputMode::Command {
-                            if self.selector.block_ptr < (self.block_list.len() - 1) as u8 {
-                                self.selector.block_ptr += 1;
-                                self.selector.task_ptr = 0;
-                                self.term.clear_screen();
-                            }
-                        }
+                        self.flow_end = Local::now().date_naive();
+                        self.input_state = InputMode::ViewingFlow;
                     }
 
-                    // MOVE SELECTOR LEFT
+                    // CREATE TASK
                     KeyEvent {
-                        code: KeyCode::Left,
+                        code: KeyCode::Char('c'),
+                        modifiers: KeyModifiers::NONE,
                         kind: KeyEventKind::Press,
                         ..
                     } => {
-                        if self.input_state == InputMode::Command {
-                            if self.selector.block_ptr > 0 {
-                                self.selector.block_ptr -= 1;
-                                self.selector.task_ptr = 0;
-                                self.term.clear_screen();
-                            }
-                        }
+                        self.tmp_task.priority = Priority::Normal;
+                        self.tmp_task.description = String::new();
+                        self.tmp_task.due = None;
+                        self.tmp_task.checklist = vec![];
+                        self.tmp_task.blocked_by = vec![];
+                        //New tasks start with the filter tags and the current user so they
+                        //stay on show
+                        self.tmp_task.tags = self.filter.clone();
+                        self.tmp_task.assignees = match &self.user {
+                            Some(user) if self.only_mine => vec![user.clone()],
+                            _ => vec![],
+                        };
+                        self.open_prompt(InputMode::WritingTask, WritingTaskType::WritingTitle, "");
                     }
 
-                    // QUICK PUSH RIGHT
+                    // EDIT TASK
                     KeyEvent {
-                        code: KeyCode::Char('>'),
+                        code: KeyCode::Char('e'),
+                        modifiers: KeyModifiers::NONE,
                         kind: KeyEventKind::Press,
                         ..
                     } => {
-                        if self.input_state == InputMode::Command {
-                            if usize::from(self.selector.block_ptr + 1) < self.block_list.len() {
-                                match self.remove_task() {
-                                    Some(t) => {
-                                        self.block_list[usize::from(self.selector.block_ptr + 1)]
-                                            .task_list
-                                            .push(t);
-                                        self.is_modified = true;
-                                    }
-                                    None => {}
-                                }
-                            }
+                        if let Some(task_ptr) = self.selected_task() {
+                            let task = &self.block_list[self.block_ptr()].task_list[task_ptr];
+                            self.tmp_task = task.clone();
+                            let title = task.title.clone();
+                            self.open_prompt(
+                                InputMode::EditingTask,
+                                WritingTaskType::WritingTitle,
+                                &title,
+                            );
                         }
-                        self.term.clear_screen();
                     }
 
-                    // QUICK PUSH LEFT
+                    // DELETE TASK
                     KeyEvent {
-                        code: KeyCode::Char('<'),
+                        code: KeyCode::Char('d'),
+                        modifiers: KeyModifiers::NONE,
                         kind: KeyEventKind::Press,
                         ..
                     } => {
-                        if self.input_state == InputMode::Command {
-                            match self.selector.block_ptr.checked_sub(1) {
-                                Some(_) => match self.remove_task() {
-                                    Some(t) => {
-                                        self.block_list[usize::from(self.selector.block_ptr - 1)]
-                                            .task_list
-                                            .push(t);
-                                        self.is_modified = true;
-                                    }
-                                    None => {}
-                                },
-                                None => {}
-                            }
-                        }
-                        self.term.clear_screen();
+                        self.delete_task();
+                    }
+
+                    // RAISE PRIORITY
+                    KeyEvent {
+                        code: KeyCode::Char('+'),
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.change_priority(true);
+                    }
+
+                    // LOWER PRIORITY
+                    KeyEvent {
+                        code: KeyCode::Char('-'),
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.change_priority(false);
+                    }
+
+                    // SORT BY PRIORITY
+                    KeyEvent {
+                        code: KeyCode::Char('P'),
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.sort_block();
+                    }
+
+                    // UNDO
+                    KeyEvent {
+                        code: KeyCode::Char('u'),
+                        modifiers: KeyModifiers::NONE,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.undo();
                     }
 
-                    _ => {}
+                    // REDO
+                    KeyEvent {
+                        code: KeyCode::Char('r'),
+                        modifiers: KeyModifiers::CONTROL,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.redo();
+                    }
+
+                    // SEE TASK
+                    KeyEvent {
+                        code: KeyCode::Enter,
+                        modifiers: KeyModifiers::NONE,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.showing_task = !self.showing_task;
+                        self.task_scroll = 0;
+                    }
+
+                    // CHECKLIST
+                    KeyEvent {
+                        code: KeyCode::Char('l'),
+                        modifiers: KeyModifiers::NONE,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } if self.selected_task().is_some() => {
+                        self.showing_task = true;
+                        self.checklist_ptr = 0;
+                        self.input_state = InputMode::ViewingChecklist;
+                    }
+
+                    // BLOCKERS
+                    KeyEvent {
+                        code: KeyCode::Char('b'),
+                        modifiers: KeyModifiers::NONE,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.open_blockers();
+                    }
+
+                    // LINKS
+                    KeyEvent {
+                        code: KeyCode::Char('g'),
+                        modifiers: KeyModifiers::NONE,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } if self.selected_task().is_some() => {
+                        self.showing_task = true;
+                        self.links_ptr = 0;
+                        self.input_state = InputMode::ViewingLinks;
+                    }
+
+                    // HISTORY
+                    KeyEvent {
+                        code: KeyCode::Char('h'),
+                        modifiers: KeyModifiers::NONE,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } if self.selected_task().is_some() => {
+                        self.showing_task = true;
+                        self.history_scroll = 0;
+                        self.input_state = InputMode::ViewingHistory;
+                    }
+
+                    // SCROLL TASK
+                    KeyEvent {
+                        code: KeyCode::PageUp,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } if self.showing_task => {
+                        self.task_scroll = self.task_scroll.saturating_sub(1);
+                    }
+                    KeyEvent {
+                        code: KeyCode::PageDown,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } if self.showing_task => {
+                        self.task_scroll += 1;
+                    }
+
+                    // MOVE SELECTOR UP
+                    KeyEvent {
+                        code: KeyCode::Up,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.step_selector(false);
+                    }
+
+                    // MOVE SELECTOR DOWN
+                    KeyEvent {
+                        code: KeyCode::Down,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.step_selector(true);
+                    }
+
+                    // MOVE SELECTOR RIGHT
+                    KeyEvent {
+                        code: KeyCode::Right,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } if self.block_ptr() < self.block_list.len() - 1 => {
+                        self.point_at(self.block_ptr() + 1, 0);
+                        self.fix_selector();
+                    }
+
+                    // MOVE SELECTOR LEFT
+                    KeyEvent {
+                        code: KeyCode::Left,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } if self.block_ptr() > 0 => {
+                        self.point_at(self.block_ptr() - 1, 0);
+                        self.fix_selector();
+                    }
+
+                    // QUICK PUSH RIGHT
+                    KeyEvent {
+                        code: KeyCode::Char('>'),
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        let target_block = self.block_ptr() + 1;
+                        if target_block < self.block_list.len() {
+                            //Moving on is allowed, but not without a word about what it waits on
+                            let waiting = self.selected_task().map_or(vec![], |task_ptr| {
+                                self.pending_blockers(
+                                    &self.block_list[self.block_ptr()].task_list[task_ptr],
+                                )
+                            });
+                            self.shift_task(target_block);
+                            if !waiting.is_empty() {
+                                let ids: Vec<String> =
+                                    waiting.iter().map(|id| format!("#{}", id)).collect();
+                                self.status = Some(format!(
+                                    "Warning: still blocked by {}, not completed yet",
+                                    ids.join(", ")
+                                ));
+                            }
+                        }
+                    }
+
+                    // QUICK PUSH LEFT
+                    KeyEvent {
+                        code: KeyCode::Char('<'),
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        if let Some(target_block) = self.block_ptr().checked_sub(1) {
+                            self.shift_task(target_block);
+                        }
+                    }
+
+                    // CREATE BLOCK
+                    KeyEvent {
+                        code: KeyCode::Char('A'),
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.open_prompt(
+                            InputMode::WritingBlock,
+                            WritingTaskType::WritingTitle,
+                            "",
+                        );
+                    }
+
+                    // RENAME BLOCK
+                    KeyEvent {
+                        code: KeyCode::Char('R'),
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        let title = self.block_list[self.block_ptr()].title.clone();
+                        self.open_prompt(
+                            InputMode::RenamingBlock,
+                            WritingTaskType::WritingTitle,
+                            &title,
+                        );
+                    }
+
+                    // DELETE BLOCK
+                    KeyEvent {
+                        code: KeyCode::Char('D'),
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.remove_block();
+                    }
+
+                    // MOVE BLOCK LEFT
+                    KeyEvent {
+                        code: KeyCode::Char('{'),
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        if let Some(target_block) = self.block_ptr().checked_sub(1) {
+                            self.move_block(target_block);
+                        }
+                    }
+
+                    // MOVE BLOCK RIGHT
+                    KeyEvent {
+                        code: KeyCode::Char('}'),
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.move_block(self.block_ptr() + 1);
+                    }
+
+                    _ => {}
+                }
+            }
+        } else if self.input_state == InputMode::ConfirmingQuit {
+            if let Event::Key(key) = event {
+                match key {
+                    // SAVE AND QUIT
+                    KeyEvent {
+                        code: KeyCode::Char('s'),
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.input_state = InputMode::Command;
+                        if self.file_path.is_some() {
+                            self.quit = self.save_or_report();
+                        } else {
+                            self.quit_after_save = true;
+                            self.open_save_as();
+                        }
+                    }
+
+                    // DISCARD AND QUIT
+                    KeyEvent {
+                        code: KeyCode::Char('d'),
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.quit = true;
+                    }
+
+                    // CANCEL, a second Ctrl-C leaves the question open
+                    KeyEvent {
+                        code: KeyCode::Char('c'),
+                        modifiers: KeyModifiers::NONE,
+                        kind: KeyEventKind::Press,
+                        ..
+                    }
+                    | KeyEvent {
+                        code: KeyCode::Esc,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.input_state = InputMode::Command;
+                    }
+
+                    _ => {}
+                }
+            }
+        } else if self.input_state == InputMode::ViewingAgenda {
+            if let Event::Key(key) = event {
+                match key {
+                    KeyEvent {
+                        code: KeyCode::Up,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.agenda_ptr = self.agenda_ptr.saturating_sub(1);
+                    }
+                    KeyEvent {
+                        code: KeyCode::Down,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } if self.agenda_ptr + 1 < self.agenda().len() => {
+                        self.agenda_ptr += 1;
+                    }
+                    KeyEvent {
+                        code: KeyCode::Enter,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        if let Some((block_ptr, task_ptr)) =
+                            self.agenda().get(self.agenda_ptr).copied()
+                        {
+                            self.point_at(block_ptr, task_ptr);
+                        }
+                        self.input_state = InputMode::Command;
+                    }
+                    KeyEvent {
+                        code: KeyCode::Esc | KeyCode::Char('a'),
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.input_state = InputMode::Command;
+                    }
+                    _ => {}
+                }
+            }
+        } else if self.input_state == InputMode::ViewingChecklist {
+            if let Event::Key(key) = event {
+                match key {
+                    // MOVE ITEM
+                    KeyEvent {
+                        code: code @ (KeyCode::Up | KeyCode::Down),
+                        modifiers: KeyModifiers::SHIFT,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.move_item(code == KeyCode::Down);
+                    }
+
+                    // SELECT ITEM
+                    KeyEvent {
+                        code: KeyCode::Up,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.checklist_ptr = self.checklist_ptr.saturating_sub(1);
+                    }
+                    KeyEvent {
+                        code: KeyCode::Down,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } if self.checklist_ptr + 1 < self.checklist_len() => {
+                        self.checklist_ptr += 1;
+                    }
+
+                    // TOGGLE ITEM
+                    KeyEvent {
+                        code: KeyCode::Char(' '),
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.toggle_item();
+                    }
+
+                    // ADD ITEM
+                    KeyEvent {
+                        code: KeyCode::Char('a'),
+                        modifiers: KeyModifiers::NONE,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.item_edit = None;
+                        self.open_prompt(InputMode::WritingItem, WritingTaskType::WritingTitle, "");
+                    }
+
+                    // EDIT ITEM
+                    KeyEvent {
+                        code: KeyCode::Char('e') | KeyCode::Enter,
+                        modifiers: KeyModifiers::NONE,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        let ptr = self.checklist_ptr;
+                        if let Some(item) = self.checklist().and_then(|c| c.get(ptr)) {
+                            let text = item.text.clone();
+                            self.item_edit = Some(ptr);
+                            self.open_prompt(
+                                InputMode::WritingItem,
+                                WritingTaskType::WritingTitle,
+                                &text,
+                            );
+                        }
+                    }
+
+                    // DELETE ITEM
+                    KeyEvent {
+                        code: KeyCode::Char('d'),
+                        modifiers: KeyModifiers::NONE,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.delete_item();
+                    }
+
+                    // CLOSE CHECKLIST
+                    KeyEvent {
+                        code: KeyCode::Esc | KeyCode::Char('l'),
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.input_state = InputMode::Command;
+                    }
+                    _ => {}
+                }
+            }
+        } else if self.input_state == InputMode::ViewingLinks {
+            if let Event::Key(key) = event {
+                match key {
+                    KeyEvent {
+                        code: KeyCode::Up,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.links_ptr = self.links_ptr.saturating_sub(1);
+                    }
+                    KeyEvent {
+                        code: KeyCode::Down,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } if self.links_ptr + 1 < self.links().len() => {
+                        self.links_ptr += 1;
+                    }
+
+                    // GO TO TASK
+                    KeyEvent {
+                        code: KeyCode::Enter,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        if let Some((id, _)) = self.links().get(self.links_ptr) {
+                            self.go_to_task(*id);
+                        }
+                    }
+
+                    // BLOCKERS
+                    KeyEvent {
+                        code: KeyCode::Char('b'),
+                        modifiers: KeyModifiers::NONE,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.open_blockers();
+                    }
+
+                    KeyEvent {
+                        code: KeyCode::Esc | KeyCode::Char('g'),
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.input_state = InputMode::Command;
+                    }
+                    _ => {}
+                }
+            }
+        } else if self.input_state == InputMode::ViewingHistory {
+            if let Event::Key(key) = event {
+                match key {
+                    // Older events, put_history stops at the first one
+                    KeyEvent {
+                        code: KeyCode::Up,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.history_scroll += 1;
+                    }
+                    KeyEvent {
+                        code: KeyCode::Down,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.history_scroll = self.history_scroll.saturating_sub(1);
+                    }
+                    KeyEvent {
+                        code: KeyCode::Esc | KeyCode::Char('h'),
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.input_state = InputMode::Command;
+                    }
+                    _ => {}
+                }
+            }
+        } else if self.input_state == InputMode::ViewingStats {
+            if let Event::Key(key) = event {
+                match key {
+                    // EXPORT
+                    KeyEvent {
+                        code: KeyCode::Char('x'),
+                        modifiers: KeyModifiers::NONE,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        let path = self.stats_path();
+                        self.open_prompt(
+                            InputMode::WritingExport,
+                            WritingTaskType::WritingTitle,
+                            &path,
+                        );
+                    }
+                    KeyEvent {
+                        code: KeyCode::Esc | KeyCode::Char('T'),
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.input_state = InputMode::Command;
+                    }
+                    _ => {}
+                }
+            }
+        } else if self.input_state == InputMode::ViewingFlow {
+            if let Event::Key(key) = event {
+                match key {
+                    // MOVE RANGE
+                    KeyEvent {
+                        code: code @ (KeyCode::Left | KeyCode::Right),
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        let step = Days::new((self.flow_days / 4).max(1) as u64);
+                        let moved = if code == KeyCode::Left {
+                            self.flow_end.checked_sub_days(step)
+                        } else {
+                            self.flow_end.checked_add_days(step)
+                        };
+                        self.flow_end = moved
+                            .unwrap_or(self.flow_end)
+                            .min(Local::now().date_naive());
+                    }
+
+                    // ZOOM
+                    KeyEvent {
+                        code: KeyCode::Char('+'),
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.flow_days = FLOW_SPANS
+                            .iter()
+                            .rev()
+                            .find(|days| **days < self.flow_days)
+                            .copied()
+                            .unwrap_or(FLOW_SPANS[0]);
+                    }
+                    KeyEvent {
+                        code: KeyCode::Char('-'),
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.flow_days = FLOW_SPANS
+                            .iter()
+                            .find(|days| **days > self.flow_days)
+                            .copied()
+                            .unwrap_or(FLOW_SPANS[FLOW_SPANS.len() - 1]);
+                    }
+
+                    // PICK DATES
+                    KeyEvent {
+                        code: KeyCode::Char('r'),
+                        modifiers: KeyModifiers::NONE,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        let range = format!(
+                            "{} {}",
+                            self.flow_start().format("%Y-%m-%d"),
+                            self.flow_end.format("%Y-%m-%d")
+                        );
+                        self.open_prompt(
+                            InputMode::WritingRange,
+                            WritingTaskType::WritingTitle,
+                            &range,
+                        );
+                    }
+
+                    KeyEvent {
+                        code: KeyCode::Esc | KeyCode::Char('F'),
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.input_state = InputMode::Command;
+                    }
+                    _ => {}
+                }
+            }
+        } else if self.input_state == InputMode::RestoringBackup {
+            if let Event::Key(key) = event {
+                match key {
+                    KeyEvent {
+                        code: KeyCode::Up,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.backup_ptr = self.backup_ptr.saturating_sub(1);
+                    }
+                    KeyEvent {
+                        code: KeyCode::Down,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } if self.backup_ptr + 1 < self.backup_list.len() => {
+                        self.backup_ptr += 1;
+                    }
+                    KeyEvent {
+                        code: KeyCode::Enter,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.restore_backup();
+                        self.input_state = InputMode::Command;
+                    }
+                    KeyEvent {
+                        code: KeyCode::Esc,
+                        kind: KeyEventKind::Press,
+                        ..
+                    } => {
+                        self.input_state = InputMode::Command;
+                    }
+                    _ => {}
+                }
+            }
+        } else {
+            match event {
+                // The title prompt also picks the priority of the task being written
+                Event::Key(KeyEvent {
+                    code: code @ (KeyCode::Up | KeyCode::Down),
+                    kind: KeyEventKind::Press,
+                    ..
+                }) if self.writing_count == WritingTaskType::WritingTitle
+                    && matches!(
+                        self.input_state,
+                        InputMode::WritingTask | InputMode::EditingTask
+                    ) =>
+                {
+                    self.tmp_task.priority = if code == KeyCode::Up {
+                        self.tmp_task.priority.raised()
+                    } else {
+                        self.tmp_task.priority.lowered()
+                    };
+                }
+                Event::Key(key) => {
+                    let action = match self.writing_count {
+                        WritingTaskType::WritingTitle
+                        | WritingTaskType::WritingDue
+                        | WritingTaskType::WritingTags
+                        | WritingTaskType::WritingAssignees => self.line_editor.handle_key(key),
+                        WritingTaskType::WritingDescription => self.text_editor.handle_key(key),
+                    };
+                    match action {
+                        EditorAction::Confirm => self.confirm_prompt(),
+                        EditorAction::Cancel => self.cancel_prompt(),
+                        EditorAction::None => {}
+                    }
+                }
+                Event::Paste(text) => match self.writing_count {
+                    WritingTaskType::WritingTitle
+                    | WritingTaskType::WritingDue
+                    | WritingTaskType::WritingTags
+                    | WritingTaskType::WritingAssignees => self.line_editor.insert_str(&text),
+                    WritingTaskType::WritingDescription => self.text_editor.insert_str(&text),
+                },
+                _ => {}
+            }
+        }
+    }
+
+    // Opens the input line for the given mode, pre-filled with `text`
+    fn open_prompt(&mut self, input_state: InputMode, writing_count: WritingTaskType, text: &str) {
+        match writing_count {
+            WritingTaskType::WritingTitle
+            | WritingTaskType::WritingDue
+            | WritingTaskType::WritingTags
+            | WritingTaskType::WritingAssignees => {
+                if input_state == InputMode::WritingPath || input_state == InputMode::WritingExport
+                {
+                    self.line_editor.set_limit(PATH_LIMIT);
+                } else if input_state == InputMode::WritingItem {
+                    self.line_editor.set_limit(ITEM_LIMIT);
+                } else if input_state == InputMode::WritingFilter
+                    || input_state == InputMode::WritingRange
+                    || input_state == InputMode::WritingMembers
+                    || input_state == InputMode::WritingBlockers
+                    || writing_count == WritingTaskType::WritingTags
+                    || writing_count == WritingTaskType::WritingAssignees
+                {
+                    self.line_editor.set_limit(TAGS_LIMIT);
+                } else {
+                    self.line_editor.set_limit(TITLE_LIMIT);
+                }
+                self.line_editor.set_text(text);
+            }
+            WritingTaskType::WritingDescription => {
+                self.text_editor.set_text(text);
+            }
+        }
+        self.writing_count = writing_count;
+        self.input_state = input_state;
+    }
+
+    fn close_prompt(&mut self) {
+        self.line_editor.clear();
+        self.text_editor.clear();
+        self.writing_count = WritingTaskType::WritingTitle;
+        self.input_state = InputMode::Command;
+    }
+
+    fn confirm_prompt(&mut self) {
+        let text = match self.writing_count {
+            WritingTaskType::WritingTitle
+            | WritingTaskType::WritingDue
+            | WritingTaskType::WritingTags
+            | WritingTaskType::WritingAssignees => self.line_editor.text(),
+            WritingTaskType::WritingDescription => self.text_editor.text(),
+        };
+
+        match self.input_state {
+            InputMode::WritingTask | InputMode::EditingTask => {
+                // Both go through the same prompts, filled with what the task already has, and
+                // only differ once the description is confirmed
+                let mode = self.input_state;
+                match self.writing_count {
+                    WritingTaskType::WritingTitle => {
+                        self.tmp_task.title = text;
+                        let due = self
+                            .tmp_task
+                            .due
+                            .map(|due| due.format("%Y-%m-%d").to_string())
+                            .unwrap_or_default();
+                        self.open_prompt(mode, WritingTaskType::WritingDue, &due);
+                    }
+                    WritingTaskType::WritingDue => {
+                        if self.confirm_due(&text) {
+                            let tags = self.tmp_task.tags.join(" ");
+                            self.open_prompt(mode, WritingTaskType::WritingTags, &tags);
+                        }
+                    }
+                    WritingTaskType::WritingTags => {
+                        self.tmp_task.tags = parse_list(&text);
+                        let assignees = self.tmp_task.assignees.join(" ");
+                        self.open_prompt(mode, WritingTaskType::WritingAssignees, &assignees);
+                    }
+                    WritingTaskType::WritingAssignees => {
+                        if self.confirm_assignees(&text) {
+                            let description = self.tmp_task.description.clone();
+                            self.open_prompt(
+                                mode,
+                                WritingTaskType::WritingDescription,
+                                &description,
+                            );
+                        }
+                    }
+                    WritingTaskType::WritingDescription => {
+                        self.tmp_task.description = text;
+                        self.close_prompt();
+
+                        if mode == InputMode::EditingTask {
+                            self.edit_task();
+                        } else {
+                            self.create_task();
+                        }
+                    }
+                }
+            }
+            InputMode::WritingBoard => {
+                if !text.is_empty() {
+                    self.rename_board(text);
+                }
+                self.close_prompt();
+            }
+            InputMode::WritingPath => {
+                self.close_prompt();
+
+                if !text.is_empty() {
+                    let path = PathBuf::from(text);
+                    // A board that was never named takes the name of its first file
+                    if self.file_path.is_none() && self.board_name == UNTITLED {
+                        if let Some(stem) = path.file_stem() {
+                            self.board_name = stem.to_string_lossy().to_string();
+                        }
+                    }
+                    self.file_path = Some(path);
+
+                    if self.save_or_report() && self.quit_after_save {
+                        self.quit = true;
+                    }
+                }
+                self.quit_after_save = false;
+            }
+            InputMode::WritingBlock => {
+                if !text.is_empty() {
+                    self.create_block(text);
+                }
+                self.close_prompt();
+            }
+            InputMode::RenamingBlock => {
+                if !text.is_empty() {
+                    self.rename_block(text);
+                }
+                self.close_prompt();
+            }
+            InputMode::WritingFilter => {
+                self.filter = parse_list(&text);
+                self.close_prompt();
+                self.fix_selector();
+            }
+            InputMode::WritingMembers => {
+                self.set_members(parse_list(&text));
+                self.close_prompt();
+            }
+            InputMode::WritingItem => {
+                if !text.trim().is_empty() {
+                    self.save_item(text.trim().to_string());
+                }
+                self.close_prompt();
+                self.input_state = InputMode::ViewingChecklist;
+            }
+            InputMode::WritingRange => {
+                if self.confirm_range(&text) {
+                    self.close_prompt();
+                    self.input_state = InputMode::ViewingFlow;
+                }
+            }
+            InputMode::WritingExport => {
+                if !text.is_empty() {
+                    self.export_stats(PathBuf::from(text));
+                }
+                self.close_prompt();
+                self.input_state = InputMode::ViewingStats;
+            }
+            InputMode::WritingBlockers => {
+                if self.confirm_blockers(&text) {
+                    self.close_prompt();
+                    self.input_state = InputMode::ViewingLinks;
+                }
+            }
+            InputMode::Command
+            | InputMode::ConfirmingQuit
+            | InputMode::RestoringBackup
+            | InputMode::ViewingAgenda
+            | InputMode::ViewingChecklist
+            | InputMode::ViewingLinks
+            | InputMode::ViewingHistory
+            | InputMode::ViewingStats
+            | InputMode::ViewingFlow => {}
+        }
+    }
+
+    // Sets the assignees of the task being written. Only board members can be assigned, anyone
+    // else keeps the prompt open with an explanation
+    fn confirm_assignees(&mut self, text: &str) -> bool {
+        let assignees = parse_list(text);
+        let strangers: Vec<String> = assignees
+            .iter()
+            .filter(|name| !self.members.contains(name))
+            .cloned()
+            .collect();
+
+        if strangers.is_empty() {
+            self.tmp_task.assignees = assignees;
+            true
+        } else {
+            self.status = Some(format!(
+                "Assignees: {} not on the board, M edits the members",
+                strangers.join(", ")
+            ));
+            false
+        }
+    }
+
+    // Checklist of the selected task, None when no task is selected
+    fn checklist(&self) -> Option<&Vec<ChecklistItem>> {
+        let block_ptr = self.block_ptr();
+        self.selected_task()
+            .map(|task_ptr| &self.block_list[block_ptr].task_list[task_ptr].checklist)
+    }
+
+    // Only asked for to change the checklist, so the task counts as updated
+    fn checklist_mut(&mut self) -> Option<&mut Vec<ChecklistItem>> {
+        let block_ptr = self.block_ptr();
+        self.selected_task().map(|task_ptr| {
+            let task = &mut self.block_list[block_ptr].task_list[task_ptr];
+            task.touch();
+            &mut task.checklist
+        })
+    }
+
+    // Keeps the progress of the checklist in the history of the selected task
+    fn record_checklist(&mut self) {
+        let block_ptr = self.block_ptr();
+        if let Some(task_ptr) = self.selected_task() {
+            let task = &mut self.block_list[block_ptr].task_list[task_ptr];
+            let (done, total) = task.progress().unwrap_or((0, 0));
+            task.record(TaskEvent::ChecklistChanged {
+                at: Local::now(),
+                done,
+                total,
+            });
+        }
+    }
+
+    fn checklist_len(&self) -> usize {
+        self.checklist().map_or(0, Vec::len)
+    }
+
+    // Rewrites the item being edited, or adds a new one under the selected item
+    fn save_item(&mut self, text: String) {
+        if self.checklist().is_none() {
+            return;
+        }
+        let at = match self.item_edit {
+            Some(i) => i,
+            None if self.checklist_len() == 0 => 0,
+            None => self.checklist_ptr + 1,
+        };
+
+        self.checkpoint();
+        let edit = self.item_edit.is_some();
+        if let Some(checklist) = self.checklist_mut() {
+            if edit {
+                checklist[at].text = text;
+            } else {
+                checklist.insert(at, ChecklistItem { text, done: false });
+            }
+        }
+        self.record_checklist();
+        self.checklist_ptr = at;
+    }
+
+    fn toggle_item(&mut self) {
+        let ptr = self.checklist_ptr;
+        if ptr < self.checklist_len() {
+            self.checkpoint();
+            if let Some(checklist) = self.checklist_mut() {
+                checklist[ptr].done = !checklist[ptr].done;
+            }
+            self.record_checklist();
+        }
+    }
+
+    fn delete_item(&mut self) {
+        let ptr = self.checklist_ptr;
+        if ptr < self.checklist_len() {
+            self.checkpoint();
+            if let Some(checklist) = self.checklist_mut() {
+                checklist.remove(ptr);
+            }
+            self.record_checklist();
+            self.checklist_ptr = ptr.min(self.checklist_len().saturating_sub(1));
+        }
+    }
+
+    // Swaps the selected item with its neighbour, the selection follows the item
+    fn move_item(&mut self, down: bool) {
+        let ptr = self.checklist_ptr;
+        let target = if down {
+            ptr + 1
+        } else {
+            match ptr.checked_sub(1) {
+                Some(target) => target,
+                None => return,
+            }
+        };
+        if target < self.checklist_len() {
+            self.checkpoint();
+            if let Some(checklist) = self.checklist_mut() {
+                checklist.swap(ptr, target);
+            }
+            self.checklist_ptr = target;
+        }
+    }
+
+    // IDs of the block and task the pointer is on
+    fn selected_ids(&self) -> (u64, Option<u64>) {
+        let task_id = self.selected_task().and(self.selector.task_id);
+        (self.block_list[self.block_ptr()].id, task_id)
+    }
+
+    // Points at the task with `task_id` wherever it is now, or else at the top of the block
+    // with `block_id`. When neither is left the pointer stays in range
+    fn select(&mut self, block_id: u64, task_id: Option<u64>) {
+        if let Some((b, t)) = task_id.and_then(|id| self.find_task(id)) {
+            self.point_at(b, t);
+            return;
+        }
+        let block_ptr = self
+            .find_block(block_id)
+            .unwrap_or(self.block_ptr())
+            .min(self.block_list.len() - 1);
+        self.point_at(block_ptr, 0);
+    }
+
+    fn find_block(&self, id: u64) -> Option<usize> {
+        self.block_list.iter().position(|block| block.id == id)
+    }
+
+    // Block and task index of the task with the given ID
+    fn find_task(&self, id: u64) -> Option<(usize, usize)> {
+        self.block_list.iter().enumerate().find_map(|(b, block)| {
+            block
+                .task_list
+                .iter()
+                .position(|task| task.id == id)
+                .map(|t| (b, t))
+        })
+    }
+
+    // IDs of the blockers of `task` that are not completed. Links to tasks that no longer
+    // exist don't hold anything up
+    fn pending_blockers(&self, task: &Task) -> Vec<u64> {
+        task.blocked_by
+            .iter()
+            .filter(|id| {
+                self.find_task(**id).is_some_and(|(b, t)| {
+                    !matches!(
+                        self.block_list[b].task_list[t].status,
+                        TaskStatus::Completed
+                    )
+                })
+            })
+            .copied()
+            .collect()
+    }
+
+    // A task waits while one of its blockers is pending, unless it is completed itself
+    fn is_blocked(&self, task: &Task) -> bool {
+        !matches!(task.status, TaskStatus::Completed) && !self.pending_blockers(task).is_empty()
+    }
+
+    // Tasks linked to the selected one: its blockers (true) and then the tasks it blocks
+    fn links(&self) -> Vec<(u64, bool)> {
+        let task_ptr = match self.selected_task() {
+            Some(task_ptr) => task_ptr,
+            None => return vec![],
+        };
+        let task = &self.block_list[self.block_ptr()].task_list[task_ptr];
+
+        let mut links: Vec<(u64, bool)> = task
+            .blocked_by
+            .iter()
+            .filter(|id| self.find_task(**id).is_some())
+            .map(|id| (*id, true))
+            .collect();
+        for block in self.block_list.iter() {
+            for other in block.task_list.iter() {
+                if other.blocked_by.contains(&task.id) {
+                    links.push((other.id, false));
+                }
+            }
+        }
+        links
+    }
+
+    // Moves the pointer to the task with the given ID, the popup then shows that task
+    fn go_to_task(&mut self, id: u64) {
+        match self.find_task(id) {
+            Some((b, t)) if self.task_shown(&self.block_list[b].task_list[t]) => {
+                self.point_at(b, t);
+                self.links_ptr = 0;
+                self.task_scroll = 0;
+            }
+            Some(_) => self.status = Some(format!("#{} is hidden by the current view", id)),
+            None => {}
+        }
+    }
+
+    fn open_blockers(&mut self) {
+        if let Some(task_ptr) = self.selected_task() {
+            let ids: Vec<String> = self.block_list[self.block_ptr()].task_list[task_ptr]
+                .blocked_by
+                .iter()
+                .map(|id| format!("#{}", id))
+                .collect();
+            self.showing_task = true;
+            self.open_prompt(
+                InputMode::WritingBlockers,
+                WritingTaskType::WritingTitle,
+                &ids.join(" "),
+            );
+        }
+    }
+
+    // Sets the blockers of the selected task. Unknown tasks, the task itself and links that
+    // would make tasks wait on each other keep the prompt open with an explanation
+    fn confirm_blockers(&mut self, text: &str) -> bool {
+        let task_ptr = match self.selected_task() {
+            Some(task_ptr) => task_ptr,
+            None => return true,
+        };
+        let block_ptr = self.block_ptr();
+        let id = self.block_list[block_ptr].task_list[task_ptr].id;
+
+        let mut blockers = vec![];
+        for word in parse_list(text) {
+            let blocker = match word.trim_start_matches('#').parse::<u64>() {
+                Ok(blocker) if self.find_task(blocker).is_some() => blocker,
+                _ => {
+                    self.status = Some(format!("Blocked by: there is no task {}", word));
+                    return false;
+                }
+            };
+            if blocker == id {
+                self.status = Some("Blocked by: a task can't block itself".to_string());
+                return false;
+            }
+            if self.waits_on(blocker, id) {
+                self.status = Some(format!("Blocked by: #{} already waits on #{}", blocker, id));
+                return false;
+            }
+            blockers.push(blocker);
+        }
+
+        if blockers != self.block_list[block_ptr].task_list[task_ptr].blocked_by {
+            self.checkpoint();
+            let task = &mut self.block_list[block_ptr].task_list[task_ptr];
+            task.record(TaskEvent::BlockersChanged {
+                at: Local::now(),
+                blocked_by: blockers.clone(),
+            });
+            task.blocked_by = blockers;
+        }
+        true
+    }
+
+    // Whether task `id` is held up by task `other`, directly or through other blockers
+    fn waits_on(&self, id: u64, other: u64) -> bool {
+        let mut pending = vec![id];
+        let mut seen = vec![];
+        while let Some(current) = pending.pop() {
+            if seen.contains(&current) {
+                continue;
+            }
+            seen.push(current);
+            if let Some((b, t)) = self.find_task(current) {
+                let blockers = &self.block_list[b].task_list[t].blocked_by;
+                if blockers.contains(&other) {
+                    return true;
                 }
+                pending.extend(blockers.iter().copied());
             }
-        } else if self.input_state == InputMode::WritingTask
-            || self.input_state == InputMode::WritingBoard
-        {
-            if let Event::Key(key) = read().unwrap() {
-                match key {
-                    // CONFIRMATION
-                    KeyEvent {
-                        code: KeyCode::Enter,
-                        modifiers: KeyModifiers::NONE,
-                        kind: KeyEventKind::Press,
-                        ..
-                    } => {
-                        if self.input_state == InputMode::WritingTask {
-                            match self.writing_count {
-                                WritingTaskType::WritingTitle => {
-                                    self.tmp_task.title = self.writing_string.clone();
-                                    self.writing_string = "".to_string();
-                                    self.writing_count = WritingTaskType::WritingDescription;
-                                }
-                                WritingTaskType::WritingDescription => {
-                                    self.tmp_task.description = self.writing_string.clone();
-                                    self.writing_string = "".to_string();
-                                    self.writing_count = WritingTaskType::WritingTitle;
-                                    self.input_state = InputMode::Command;
+        }
+        false
+    }
 
-                                    self.create_task();
-                                    self.is_modified = true;
-                                }
-                            }
-                        } else if self.input_state == InputMode::WritingBoard {
-                            match self.writing_count {
-                                WritingTaskType::WritingTitle => {
-                                    self.board_name = self.writing_string.clone();
-                                    self.writing_string = "".to_string();
-                                    self.writing_count = WritingTaskType::WritingTitle;
-                                    self.input_state = InputMode::Command;
-
-                                    self.save();
-                                    self.is_modified = false;
-                                    self.term.clear_screen();
-                                }
-                                _ => {}
-                            }
-                        }
-                    }
+    // Replaces the roster. Whoever leaves it is unassigned from every task
+    fn set_members(&mut self, members: Vec<String>) {
+        if members == self.members {
+            return;
+        }
 
-                    // WRITE CHARS
-                    KeyEvent {
-                        code: KeyCode::Char(key),
-                        kind: KeyEventKind::Press,
-                        ..
-                    } => {
-                        if self.input_state == InputMode::WritingTask
-                            || self.input_state == InputMode::WritingBoard
-                        {
-                            match self.writing_count {
-                                WritingTaskType::WritingTitle => {
-                                    if self.writing_string.len() < 15 {
-                                        //15 is the limit
-                                        self.writing_string.push(key);
-                                    }
-                                }
-                                WritingTaskType::WritingDescription => {
-                                    if self.writing_string.len() < 250 {
-                                        //250 is the limit of characters
-                                        self.writing_string.push(key);
-                                    }
-                                }
-                            }
-                        }
-                    }
+        self.checkpoint();
+        for block in self.block_list.iter_mut() {
+            for task in block.task_list.iter_mut() {
+                task.assignees.retain(|name| members.contains(name));
+            }
+        }
+        self.members = members;
+    }
 
-                    // ERASE CHARS
-                    KeyEvent {
-                        code: KeyCode::Backspace,
-                        kind: KeyEventKind::Press,
-                        ..
-                    } => {
-                        if self.input_state == InputMode::WritingTask
-                            || self.input_state == InputMode::WritingBoard
-                        {
-                            if self.writing_string.len() > 0 {
-                                self.writing_string.pop();
-                            }
-                        }
-                    }
+    // Sets the due date of the task being written. Input that isn't a date keeps the prompt
+    // open with an explanation
+    fn confirm_due(&mut self, text: &str) -> bool {
+        if text.trim().is_empty() {
+            self.tmp_task.due = None;
+            return true;
+        }
 
-                    _ => {}
-                }
+        match parse_due(text, Local::now().date_naive()) {
+            Some(due) => {
+                self.tmp_task.due = Some(due);
+                true
+            }
+            None => {
+                self.status = Some(format!("Due: \"{}\" is not a date", text.trim()));
+                false
             }
         }
     }
 
+    // Leaves the prompt without touching the board
+    fn cancel_prompt(&mut self) {
+        self.quit_after_save = false;
+        let return_to = match self.input_state {
+            InputMode::WritingItem => InputMode::ViewingChecklist,
+            InputMode::WritingBlockers => InputMode::ViewingLinks,
+            InputMode::WritingExport => InputMode::ViewingStats,
+            InputMode::WritingRange => InputMode::ViewingFlow,
+            _ => InputMode::Command,
+        };
+        self.close_prompt();
+        //Prompts opened from a popup go back to it
+        self.input_state = return_to;
+    }
+
     fn put_board_name(&mut self) {
         let mut board_str: String = self.board_name.clone();
 
-        if self.is_modified == true {
+        if self.is_modified {
             board_str.push('*');
-        } else {
-            board_str = self.board_name.clone();
         }
 
         self.term.put_str(
             board_str,
-            (self.term.get_size().width / 2) as u16 + (self.board_name.len() / 2) as u16,
+            (self.term.get_size().width / 2).saturating_sub((self.board_name.width() / 2) as u16),
             0,
             Color::Black,
             Color::White,
         );
+
+        let mut view = vec![];
+        if let (true, Some(user)) = (self.only_mine, &self.user) {
+            view.push(format!("My tasks ({})", user));
+        }
+        if !self.filter.is_empty() {
+            view.push(format!("Filter: {}", self.filter.join(", ")));
+        }
+        if !view.is_empty() {
+            let filter_str = view.join(" | ");
+            self.term.put_str(
+                self.trim_str(
+                    filter_str.clone(),
+                    self.term.get_size().width.saturating_sub(2).into(),
+                ),
+                (self.term.get_size().width / 2).saturating_sub((filter_str.width() / 2) as u16),
+                1,
+                Color::Black,
+                Color::Yellow,
+            );
+        }
     }
 
     fn draw_board(&mut self) {
+        self.fix_selector();
+        self.layout_blocks();
+
         self.put_board_name();
 
-        self.put_block(0);
+        self.put_block();
     }
 
     fn trim_str(&self, trim_str: String, trim_index: usize) -> String {
         //starting pos x
         //block x +1
-        if trim_str.len() > trim_index {
-            let ts = trim_str.clone();
-            let mut s: String = ts[..trim_index].into();
+        if trim_str.width() > trim_index {
+            let mut s = String::new();
+            for c in trim_str.chars() {
+                if s.width() + c.width().unwrap_or(0) > trim_index {
+                    break;
+                }
+                s.push(c);
+            }
             s.push('-');
             return s;
         }
         trim_str
     }
 
-    fn hit_block_bottom(&self, line: usize) -> bool {
-        //Bottom is line set 7
-        if line >= 7 {
-            return true;
-        }
-        false
-    }
-
     fn put_tasks(&mut self) {
-        for block in self.block_list.iter() {
-            let mut it = 0;
-            let starting_task: usize = self.selector.task_ptr.into();
-            if block.task_list.len() > 0 {
-                if self.block_list[self.selector.block_ptr as usize].title == block.title {
-                    //Rendering the selector block
-                    for i in starting_task..block.task_list.len() {
-                        //if task position didn't hit the bottom of block
-                        // Draws Title
-                        if it < 6 {
-                            self.term.put_str(
-                                self.trim_str(
-                                    block.task_list[i].title.clone(),
-                                    (block.size.width - 6).into(),
-                                ),
-                                block.position.x + 5,
-                                block.position.y + 2 + (4 * it),
-                                Color::White,
-                                Color::Reset,
-                            );
-                            //Draws Description
-                            self.term.put_str(
-                                self.trim_str(
-                                    block.task_list[i].description.clone(),
-                                    (block.size.width - 6).into(),
-                                ),
-                                block.position.x + 5,
-                                block.position.y + 3 + (4 * it),
-                                Color::Grey,
-                                Color::Reset,
-                            );
-
-                            it += 1;
-                        }
+        for (b, block) in self.block_list.iter().enumerate() {
+            if !self.block_shown(b) {
+                continue;
+            }
+            let selected_block = b == self.block_ptr();
+            let rows = block.visible_tasks();
+            let today = Local::now().date_naive();
+            let shown = self.shown_tasks(b);
+
+            for (row, i) in shown.iter().skip(block.scroll).take(rows).enumerate() {
+                let task = &block.task_list[*i];
+                let y = block.position.y + 2 + 4 * row as u16;
+                //Tags and assignees take the row under the title and push the description down
+                let extras = !task.tags.is_empty()
+                    || !task.assignees.is_empty()
+                    || !task.checklist.is_empty();
+                let description_y = if extras { y + 2 } else { y + 1 };
+
+                //The selected task is drawn reversed across the block
+                let (mut title_fg, description_fg, bg) = if selected_block && *i == self.task_ptr()
+                {
+                    let fill = " ".repeat((block.size.width - 3).into());
+                    for fill_y in y..=description_y {
+                        self.term.put_str(
+                            fill.clone(),
+                            block.position.x + 2,
+                            fill_y,
+                            Color::White,
+                            Color::White,
+                        );
                     }
-                    it = 0;
+                    (Color::Black, Color::DarkGrey, Color::White)
                 } else {
-                    //Rendering NOT the selector block
-                    for i in 0..block.task_list.len() {
-                        if it < 6 {
-                            //if task position didn't hit the bottom of block
-                            // Draws Title
-                            self.term.put_str(
-                                self.trim_str(
-                                    block.task_list[i].title.clone(),
-                                    (block.size.width - 6).into(),
-                                ),
-                                block.position.x + 5,
-                                block.position.y + 2 + (4 * it),
-                                Color::White,
-                                Color::Reset,
-                            );
-                            //Draws Description
-                            self.term.put_str(
-                                self.trim_str(
-                                    block.task_list[i].description.clone(),
-                                    (block.size.width - 6).into(),
-                                ),
-                                block.position.x + 5,
-                                block.position.y + 3 + (4 * it),
-                                Color::Grey,
-                                Color::Reset,
-                            );
+                    (Color::White, Color::Grey, Color::Reset)
+                };
+
+                //Tasks waiting on open blockers are marked before the priority
+                if self.is_blocked(task) {
+                    let blocked_fg = if bg == Color::White {
+                        Color::DarkRed
+                    } else {
+                        Color::Red
+                    };
+                    self.term.put_str(
+                        "\u{2298}".to_string(),
+                        block.position.x + 2,
+                        y,
+                        blocked_fg,
+                        bg,
+                    );
+                }
+                //Priority marker
+                self.term.put_str(
+                    "\u{25CF}".to_string(),
+                    block.position.x + 3,
+                    y,
+                    task.priority.color(),
+                    bg,
+                );
+                //Due date at the end of the title row, late tasks get a coloured title
+                let mut title_width = block.size.width - 6;
+                if let Some(due) = task.due {
+                    let urgency = urgency(task, today);
+                    let due_fg = urgency.color(bg == Color::White);
+                    if urgency != Urgency::Later {
+                        title_fg = due_fg;
+                    }
 
-                            it += 1;
-                        }
+                    let due_str = due.format("%b %d").to_string();
+                    let due_width = due_str.len() as u16;
+                    if title_width >= due_width + 6 {
+                        title_width -= due_width + 1;
+                        self.term.put_str(
+                            due_str,
+                            block.position.x + block.size.width - 1 - due_width,
+                            y,
+                            due_fg,
+                            bg,
+                        );
+                    }
+                }
+
+                // Draws Title
+                self.term.put_str(
+                    self.trim_str(task.title.clone(), title_width.into()),
+                    block.position.x + 5,
+                    y,
+                    title_fg,
+                    bg,
+                );
+                //Assignee badges at the end of the row, as many as fit
+                let mut badge_x = block.position.x + block.size.width - 1;
+                for name in task.assignees.iter() {
+                    let badge = initials(name);
+                    let badge_width = badge.width() as u16;
+                    if badge_x < block.position.x + 5 + badge_width {
+                        break;
+                    }
+                    badge_x -= badge_width;
+                    self.term
+                        .put_str(badge, badge_x, y + 1, Color::Black, Color::Cyan);
+                    badge_x -= 1;
+                }
+                //Checklist progress before the badges, green once every item is done
+                if let Some((done, total)) = task.progress() {
+                    let progress = format!("{}/{}", done, total);
+                    let progress_width = progress.len() as u16;
+                    if badge_x >= block.position.x + 5 + progress_width {
+                        badge_x -= progress_width;
+                        let progress_fg = match (done == total, bg == Color::White) {
+                            (true, true) => Color::DarkGreen,
+                            (true, false) => Color::Green,
+                            (false, _) => description_fg,
+                        };
+                        self.term.put_str(progress, badge_x, y + 1, progress_fg, bg);
+                        badge_x -= 1;
+                    }
+                }
+                //Tag chips, as many as fit before the badges
+                let mut chip_x = block.position.x + 5;
+                for tag in task.tags.iter() {
+                    let chip = format!(" {} ", tag);
+                    let chip_width = chip.width() as u16;
+                    if chip_x + chip_width > badge_x {
+                        break;
                     }
+                    self.term
+                        .put_str(chip, chip_x, y + 1, Color::Black, self.tag_color(tag));
+                    chip_x += chip_width + 1;
                 }
+                //Draws Description
+                self.term.put_str(
+                    self.trim_str(
+                        task.description.replace('\n', " "),
+                        (block.size.width - 6).into(),
+                    ),
+                    block.position.x + 5,
+                    description_y,
+                    description_fg,
+                    bg,
+                );
+            }
+
+            //Scroll indicators on the borders
+            if block.scroll > 0 {
+                self.term.put_str(
+                    "\u{25B2}".to_string(),
+                    block.position.x + 2,
+                    block.position.y,
+                    Color::White,
+                    Color::Reset,
+                );
+            }
+            if block.scroll + rows < shown.len() {
+                self.term.put_str(
+                    "\u{25BC}".to_string(),
+                    block.position.x + 2,
+                    block.position.y + block.size.height,
+                    Color::White,
+                    Color::Reset,
+                );
             }
         }
     }
 
-    fn put_block(&mut self, block_index: usize) {
+    fn put_block(&mut self) {
         for (i, block) in self.block_list.iter().enumerate() {
+            if !self.block_shown(i) {
+                continue;
+            }
+
             //Put Name
             self.term.put_str(
-                block.title.clone(),
-                block.position.x - 1 + block.size.width / 2,
+                self.trim_str(block.title.clone(), (block.size.width - 2).into()),
+                block.position.x
+                    + 1
+                    + block.size.width.saturating_sub(block.title.width() as u16) / 2,
                 block.position.y - 2,
                 Color::Black,
                 Color::White,
             );
 
             //Put Rect
-            let mut color: Color = Color::White;
-            match i {
-                0 => {
-                    color = Color::Red;
-                }
-                1 => {
-                    color = Color::Yellow;
-                }
-                2 => {
-                    color = Color::Green;
-                }
-                _ => {}
-            }
-
             self.term.draw_rect(
                 block.position.x,
                 block.position.y,
                 block.size.width,
                 block.size.height,
-                color,
+                Block::color(i),
                 None,
             );
 
             //Task Index
-            if self.selector.block_ptr as usize == i {
+            //Only the tasks on show are counted
+            let end = self.shown_tasks(i).len();
+            if self.block_ptr() == i {
                 //Pointer on block
+                let initial = self.shown_position(i).map_or(0, |pos| pos + 1);
                 self.term.put_str(
-                    format!("{}/{}", self.selector.task_ptr + 1, block.task_list.len()),
+                    format!("{}/{}", initial, end),
                     block.position.x + block.size.width - 4,
                     block.position.y,
                     Color::Red,
@@ -771,12 +3192,7 @@ impl Board {
                 );
             } else {
                 //Pointer not on block
-                let mut initial = 0;
-                let mut end = 0;
-                if block.task_list.len() > 0 {
-                    initial = 1;
-                    end = block.task_list.len();
-                }
+                let initial = if end > 0 { 1 } else { 0 };
                 self.term.put_str(
                     format!("{}/{}", initial, end),
                     block.position.x + block.size.width - 4,
@@ -787,63 +3203,546 @@ impl Board {
             }
         }
 
+        //Blocks scrolled off either side
+        let arrow_y = self.block_list[0].position.y + self.block_list[0].size.height / 2;
+        if self.block_scroll > 0 {
+            self.term.put_str(
+                "\u{25C0}".to_string(),
+                0,
+                arrow_y,
+                Color::White,
+                Color::Reset,
+            );
+        }
+        if self.block_scroll + self.blocks_shown < self.block_list.len() {
+            self.term.put_str(
+                "\u{25B6}".to_string(),
+                self.term.get_size().width - 1,
+                arrow_y,
+                Color::White,
+                Color::Reset,
+            );
+        }
+
         //Draw Tasks
         self.put_tasks();
     }
 
     fn put_bottom_bar(&mut self, bar_str: String) {
-        let spaces: String = " ".repeat(self.term.get_size().width as usize);
-        self.term.move_cursor(0, self.term.get_size().height - 3);
-        self.term.clear_current_line();
-        print!("{}", spaces.black().on_white());
+        let width = self.term.get_size().width;
+        let spaces: String = " ".repeat(width as usize);
+        self.term
+            .put_str(spaces, 0, self.bar_row(), Color::Black, Color::White);
+
+        let saved_str = match self.last_saved {
+            Some(time) => format!("saved {}", time.format("%H:%M:%S")),
+            None => "not saved".to_string(),
+        };
+        let saved_x = width.saturating_sub(saved_str.len() as u16 + 1);
+
+        //Hints give way to the save time on narrow terminals
         self.term.put_str(
-            bar_str,
+            self.trim_str(bar_str, saved_x.saturating_sub(2).into()),
             0,
-            self.term.get_size().height - 3,
+            self.bar_row(),
+            Color::Black,
+            Color::White,
+        );
+        self.term.put_str(
+            saved_str,
+            saved_x,
+            self.bar_row(),
             Color::Black,
             Color::White,
         );
     }
 
-    fn check_save(&mut self) -> bool {
-        Path::new(&format!("{}.json", self.board_name.clone())).exists()
+    // Asks where to save, starting from the current file or a name based on the board
+    fn open_save_as(&mut self) {
+        let path = match &self.file_path {
+            Some(p) => p.to_string_lossy().to_string(),
+            None => format!("{}.json", self.board_name),
+        };
+        self.open_prompt(InputMode::WritingPath, WritingTaskType::WritingTitle, &path);
+    }
+
+    fn save(&mut self) -> Result<(), BoardError> {
+        let path = self.file_path.clone().ok_or(BoardError::NoPath)?;
+        self.write_board(&path, self.config.backups)?;
+
+        self.saved_revision = self.revision;
+        self.is_modified = false;
+        self.last_saved = Some(Local::now());
+        Ok(())
+    }
+
+    // Saves the board, a failure is shown in the status bar and leaves the board modified
+    fn save_or_report(&mut self) -> bool {
+        match self.save() {
+            Ok(()) => {
+                self.status = Some(format!("Saved {}", self.file_path_str()));
+                true
+            }
+            Err(e) => {
+                self.status = Some(format!("Could not save {}: {}", self.file_path_str(), e));
+                false
+            }
+        }
+    }
+
+    fn file_path_str(&self) -> String {
+        match &self.file_path {
+            Some(p) => p.display().to_string(),
+            None => "".to_string(),
+        }
+    }
+
+    fn write_board(&self, path: &Path, backups: usize) -> Result<(), BoardError> {
+        let document = BoardDocument::new(
+            self.board_name.clone(),
+            self.members.clone(),
+            self.next_id,
+            self.block_list.clone(),
+        );
+
+        write_atomic(path, &document.to_json()?.into_bytes(), backups)?;
+        Ok(())
+    }
+
+    fn open_backups(&mut self) {
+        self.backup_list = match &self.file_path {
+            Some(path) => list_backups(path, self.config.backups),
+            None => vec![],
+        };
+        self.backup_ptr = 0;
+
+        if self.backup_list.is_empty() {
+            self.status = Some("There are no backups of this board".to_string());
+        } else {
+            self.input_state = InputMode::RestoringBackup;
+        }
     }
 
-    fn save(&mut self) {
-        let args: Vec<String> = env::args().collect();
-        let board_json: serde_json::Value;
+    fn show_backups(&mut self) {
+        let (boxx, boxy, boxw, boxh) = self.task_popup();
+
+        self.term
+            .draw_rect(boxx, boxy, boxw, boxh, Color::White, Some(Color::White));
+        self.term.put_str(
+            "Backups".to_string(),
+            boxx + 1,
+            boxy,
+            Color::Black,
+            Color::White,
+        );
 
-        board_json = json!({
-        "board-name":self.board_name,
+        let now = SystemTime::now();
+        for (line, (path, modified)) in self.backup_list.iter().enumerate() {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let age = now
+                .duration_since(*modified)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            let age = match age {
+                0..=59 => "just now".to_string(),
+                60..=3599 => format!("{} min ago", age / 60),
+                3600..=86399 => format!("{} h ago", age / 3600),
+                _ => format!("{} days ago", age / 86400),
+            };
+
+            let (fg, bg) = if line == self.backup_ptr {
+                (Color::White, Color::Black)
+            } else {
+                (Color::Black, Color::White)
+            };
+            self.term.put_str(
+                self.trim_str(format!("{:<30} {}", name, age), (boxw - 3).into()),
+                boxx + 2,
+                boxy + 2 + line as u16,
+                fg,
+                bg,
+            );
+        }
+    }
 
-            "blocks":{
-                "todo":self.block_list[0].task_list,
-                "active":self.block_list[1].task_list,
-                "completed":self.block_list[2].task_list
+    // Replaces the board with the selected backup. It counts as a change, so it can be undone
+    // and isn't written to the board file until the next save
+    fn restore_backup(&mut self) {
+        let (path, _) = self.backup_list[self.backup_ptr].clone();
+
+        let document = std::fs::read_to_string(&path)
+            .map_err(BoardError::from)
+            .and_then(|contents| BoardDocument::from_json(&contents));
+
+        match document {
+            Ok(document) => {
+                self.checkpoint();
+                //IDs only move forward, tasks undone back into the board keep theirs
+                self.next_id = self.next_id.max(document.first_free_id());
+                if !document.board_name.is_empty() {
+                    self.board_name = document.board_name;
+                }
+                self.members = document.members;
+                self.block_list = document.blocks;
+                self.point_at(0, 0);
+                self.refresh_task_status();
+                self.status = Some(format!("Restored {}", path.display()));
+            }
+            Err(e) => {
+                self.status = Some(format!("Could not restore {}: {}", path.display(), e));
             }
+        }
+    }
+
+    // Every task as block and task index, the ones due first at the top and the ones without
+    // a due date last, in board order
+    fn agenda(&self) -> Vec<(usize, usize)> {
+        let mut entries: Vec<(usize, usize)> = self
+            .block_list
+            .iter()
+            .enumerate()
+            .flat_map(|(b, block)| (0..block.task_list.len()).map(move |t| (b, t)))
+            .filter(|(b, t)| self.task_shown(&self.block_list[*b].task_list[*t]))
+            .collect();
+        entries.sort_by_key(|(b, t)| {
+            let due = self.block_list[*b].task_list[*t].due;
+            (due.is_none(), due)
         });
+        entries
+    }
+
+    fn open_agenda(&mut self) {
+        if self.agenda().is_empty() {
+            self.status = Some("There are no tasks on show".to_string());
+        } else {
+            self.agenda_ptr = 0;
+            self.input_state = InputMode::ViewingAgenda;
+        }
+    }
 
-        let board_json_str = serde_json::to_string_pretty(&board_json).unwrap();
+    fn show_agenda(&mut self) {
+        let (boxx, boxy, boxw, boxh) = self.popup(76, self.bar_row());
+
+        self.term
+            .draw_rect(boxx, boxy, boxw, boxh, Color::White, Some(Color::White));
+        self.term.put_str(
+            "Agenda".to_string(),
+            boxx + 1,
+            boxy,
+            Color::Black,
+            Color::White,
+        );
+
+        let rows = (boxh - 3) as usize;
+        let today = Local::now().date_naive();
+        let entries = self.agenda();
+        let scroll = (self.agenda_ptr + 1).saturating_sub(rows);
+
+        for (line, (b, t)) in entries.iter().skip(scroll).take(rows).enumerate() {
+            let block = &self.block_list[*b];
+            let task = &block.task_list[*t];
+            let date_str = match task.due {
+                Some(due) => due.format("%a %Y-%m-%d").to_string(),
+                None => "no due date".to_string(),
+            };
+            let row = format!(
+                "{:<16}{:<17}{}",
+                date_str,
+                self.trim_str(block.title.clone(), 15),
+                task.title
+            );
 
-        let mut file = File::create(self.board_name.clone() + &".json".to_string()).unwrap();
-        file.write_all(&board_json_str.into_bytes()).unwrap();
+            let (fg, bg) = if scroll + line == self.agenda_ptr {
+                (Color::White, Color::Black)
+            } else {
+                (urgency(task, today).color(true), Color::White)
+            };
+            self.term.put_str(
+                self.trim_str(row, (boxw - 3).into()),
+                boxx + 2,
+                boxy + 2 + line as u16,
+                fg,
+                bg,
+            );
+        }
     }
 
-    fn load(&mut self) {
-        //Throw error if the file structure doesn't satisfy the requirements
-        self.is_modified = false;
+    fn show_stats(&mut self) {
+        let stats = Stats::new(&self.block_list, Local::now().date_naive());
+        let (boxx, boxy, boxw, boxh) = self.popup(60, self.bar_row());
+
+        self.term
+            .draw_rect(boxx, boxy, boxw, boxh, Color::White, Some(Color::White));
+        self.term.put_str(
+            "Statistics".to_string(),
+            boxx + 1,
+            boxy,
+            Color::Black,
+            Color::White,
+        );
+
+        let mut lines: Vec<(String, Color)> = vec![("Tasks per block".to_string(), Color::Black)];
+        for (title, count) in stats.blocks.iter() {
+            lines.push((
+                format!("  {:<20}{:>5}", self.trim_str(title.clone(), 18), count),
+                Color::DarkGrey,
+            ));
+        }
+
+        for (heading, summary) in [
+            ("Cycle time, from in progress to done", &stats.cycle_time),
+            ("Lead time, from created to done", &stats.lead_time),
+        ] {
+            lines.push((String::new(), Color::Black));
+            lines.push((heading.to_string(), Color::Black));
+            lines.push(match summary {
+                Some(s) => (
+                    format!(
+                        "  avg {:.1}d  p50 {:.1}d  p85 {:.1}d  p95 {:.1}d  ({} tasks)",
+                        s.average, s.p50, s.p85, s.p95, s.count
+                    ),
+                    Color::DarkGrey,
+                ),
+                None => (
+                    "  no completed tasks with a recorded history".to_string(),
+                    Color::DarkGrey,
+                ),
+            });
+        }
+
+        //Weekly throughput as bars scaled to the busiest week
+        lines.push((String::new(), Color::Black));
+        lines.push(("Completed per week".to_string(), Color::Black));
+        let busiest = stats.throughput.iter().map(|(_, count)| *count).max();
+        let bar_width = usize::from(boxw).saturating_sub(18);
+        for (week, count) in stats.throughput.iter() {
+            let bar = match busiest {
+                Some(busiest) if busiest > 0 => count * bar_width / busiest,
+                _ => 0,
+            };
+            lines.push((
+                format!(
+                    "  {}  {:>3} {}",
+                    week.format("%b %d"),
+                    count,
+                    "\u{2588}".repeat(bar)
+                ),
+                Color::DarkGrey,
+            ));
+        }
+
+        for (row, (line, fg)) in lines.into_iter().take((boxh - 3).into()).enumerate() {
+            self.term.put_str(
+                self.trim_str(line, (boxw - 3).into()),
+                boxx + 2,
+                boxy + 2 + row as u16,
+                fg,
+                Color::White,
+            );
+        }
+    }
+
+    fn flow_start(&self) -> NaiveDate {
+        self.flow_end - Days::new((self.flow_days - 1) as u64)
+    }
+
+    // Sets the days shown in the flow diagram from two dates. Anything else keeps the prompt
+    // open with an explanation
+    fn confirm_range(&mut self, text: &str) -> bool {
+        let today = Local::now().date_naive();
+        let words: Vec<String> = parse_list(text);
+        let dates: Vec<Option<NaiveDate>> =
+            words.iter().map(|word| parse_due(word, today)).collect();
+
+        match dates.as_slice() {
+            [Some(from), Some(to)] if from <= to && *to <= today => {
+                let max_days = FLOW_SPANS[FLOW_SPANS.len() - 1];
+                self.flow_end = *to;
+                self.flow_days = (*to - *from).num_days() + 1;
+                if self.flow_days > max_days {
+                    self.flow_days = max_days;
+                    self.status = Some(format!(
+                        "Dates: showing the last {} days up to {}",
+                        max_days,
+                        to.format("%Y-%m-%d")
+                    ));
+                }
+                true
+            }
+            [Some(_), Some(_)] => {
+                self.status = Some(
+                    "Dates: the first day has to come before the last, which can't be after today"
+                        .to_string(),
+                );
+                false
+            }
+            _ => {
+                self.status = Some(format!("Dates: \"{}\" is not two dates", text.trim()));
+                false
+            }
+        }
+    }
+
+    // Stacked bands of the number of tasks in every block at the end of each day, the last
+    // block at the bottom. Each cell holds two bands with a half block glyph, one in each half
+    fn show_flow(&mut self) {
+        let (boxx, boxy, boxw, boxh) = self.popup(self.term.get_size().width, self.bar_row());
+        let from = self.flow_start();
+        let counts = flow(&self.block_list, from, self.flow_end);
+
+        self.term
+            .draw_rect(boxx, boxy, boxw, boxh, Color::White, Some(Color::White));
+        self.term.put_str(
+            format!(
+                "Cumulative Flow, {} to {} ({} days)",
+                from.format("%b %d %Y"),
+                self.flow_end.format("%b %d %Y"),
+                self.flow_days
+            ),
+            boxx + 1,
+            boxy,
+            Color::Black,
+            Color::White,
+        );
+
+        //Legend, in the order the bands are stacked from the top
+        let mut legend_x = boxx + 2;
+        for (i, block) in self.block_list.iter().enumerate() {
+            let label = format!(" {} ", self.trim_str(block.title.clone(), 15));
+            let label_width = label.width() as u16;
+            if legend_x + label_width + 2 > boxx + boxw {
+                break;
+            }
+            self.term.put_str(
+                "\u{2588}".to_string(),
+                legend_x,
+                boxy + 1,
+                Block::color(i),
+                Color::White,
+            );
+            self.term
+                .put_str(label, legend_x + 1, boxy + 1, Color::Black, Color::White);
+            legend_x += label_width + 2;
+        }
+
+        let most = counts
+            .iter()
+            .map(|day| day.iter().sum::<usize>())
+            .max()
+            .unwrap_or(0);
+        let chart_x = boxx + 6;
+        let chart_w = boxw.saturating_sub(7);
+        let chart_top = boxy + 3;
+        let chart_rows = boxh.saturating_sub(5);
+        if most == 0 || chart_w == 0 || chart_rows == 0 {
+            self.term.put_str(
+                "No task moves recorded in these days".to_string(),
+                boxx + 2,
+                chart_top,
+                Color::DarkGrey,
+                Color::White,
+            );
+            return;
+        }
+
+        //Axis labels
+        self.term.put_str(
+            format!("{:>4}", most),
+            boxx + 1,
+            chart_top,
+            Color::DarkGrey,
+            Color::White,
+        );
+        self.term.put_str(
+            format!("{:>4}", 0),
+            boxx + 1,
+            chart_top + chart_rows - 1,
+            Color::DarkGrey,
+            Color::White,
+        );
+        let last_label = self.flow_end.format("%b %d").to_string();
+        self.term.put_str(
+            from.format("%b %d").to_string(),
+            chart_x,
+            boxy + boxh - 1,
+            Color::DarkGrey,
+            Color::White,
+        );
+        self.term.put_str(
+            last_label.clone(),
+            chart_x + chart_w - last_label.len() as u16,
+            boxy + boxh - 1,
+            Color::DarkGrey,
+            Color::White,
+        );
+
+        let halves = usize::from(chart_rows) * 2;
+        for column in 0..chart_w {
+            //Days are stretched or sampled to fill the width
+            let day = &counts[usize::from(column) * counts.len() / usize::from(chart_w)];
+
+            //Top of every band in half rows, from the last block up
+            let mut tops = vec![];
+            let mut total = 0;
+            for index in (0..day.len()).rev() {
+                total += day[index];
+                tops.push((index, (total * halves + most / 2) / most));
+            }
+            let band_at = |half: usize| {
+                tops.iter()
+                    .find(|(_, top)| half < *top)
+                    .map(|(index, _)| Block::color(*index))
+            };
+
+            for row in 0..usize::from(chart_rows) {
+                let y = chart_top + chart_rows - 1 - row as u16;
+                match (band_at(row * 2), band_at(row * 2 + 1)) {
+                    (Some(lower), upper) => self.term.put_glyph(
+                        '\u{2584}',
+                        chart_x + column,
+                        y,
+                        lower,
+                        upper.unwrap_or(Color::White),
+                    ),
+                    (None, _) => break,
+                }
+            }
+        }
+    }
+
+    // Statistics go next to the board file by default, named after it
+    fn stats_path(&self) -> String {
+        match &self.file_path {
+            Some(p) => p.with_extension("stats.csv").to_string_lossy().to_string(),
+            None => format!("{}.stats.csv", self.board_name),
+        }
+    }
 
-        let contents = std::fs::read_to_string(self.board_name.as_str()).unwrap();
+    fn export_stats(&mut self, path: PathBuf) {
+        let csv = Stats::new(&self.block_list, Local::now().date_naive()).to_csv();
+        self.status = Some(match write_atomic(&path, csv.as_bytes(), 0) {
+            Ok(()) => format!("Statistics exported to {}", path.display()),
+            Err(e) => format!("Could not export to {}: {}", path.display(), e),
+        });
+    }
 
-        let data: Value = serde_json::from_str(&contents).unwrap();
+    fn load(&mut self) -> Result<(), BoardError> {
+        let contents = std::fs::read_to_string(self.file_path.clone().unwrap_or_default())?;
 
-        self.board_name = data["board-name"].to_string().replace("\"", "");
+        let document = BoardDocument::from_json(&contents)?;
+        self.next_id = document.first_free_id();
 
-        self.block_list[0].task_list =
-            serde_json::from_value(data["blocks"]["todo"].clone()).unwrap();
-        self.block_list[1].task_list =
-            serde_json::from_value::<Vec<Task>>(data["blocks"]["active"].clone()).unwrap();
-        self.block_list[2].task_list =
-            serde_json::from_value::<Vec<Task>>(data["blocks"]["completed"].clone()).unwrap();
+        if !document.board_name.is_empty() {
+            self.board_name = document.board_name;
+        }
+        self.members = document.members;
+        self.block_list = document.blocks;
+        self.refresh_task_status();
+        self.is_modified = false;
+        self.saved_revision = self.revision;
+        Ok(())
     }
 }
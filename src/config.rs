@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+
+use crossterm::style::Color;
+use serde::Deserialize;
+
+use crate::BoardError;
+
+// User settings read from `$XDG_CONFIG_HOME/kanban-rs/config.json`
+// (or `~/.config/kanban-rs/config.json`). Missing keys keep their default.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub backups: usize, // Number of `board.json.N` copies kept on save
+    // Seconds without changes before the board is saved on its own, 0 saves after every
+    // change and None turns autosave off
+    pub autosave: Option<u64>,
+    // Chip colour for each tag, tags missing here get one picked from their name
+    #[serde(rename = "tag-colors")]
+    pub tag_colors: HashMap<String, Color>,
+    // Member name used by the "my tasks" view, $USER when missing
+    pub user: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            backups: 3,
+            autosave: None,
+            tag_colors: HashMap::new(),
+            user: None,
+        }
+    }
+}
+
+impl Config {
+    fn path() -> Option<PathBuf> {
+        let dir = match env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        };
+        Some(dir.join("kanban-rs").join("config.json"))
+    }
+
+    pub fn load() -> Result<Self, BoardError> {
+        match Self::path() {
+            Some(path) if path.exists() => {
+                let contents = std::fs::read_to_string(path)?;
+                Ok(serde_json::from_str(&contents)?)
+            }
+            _ => Ok(Self::default()),
+        }
+    }
+}
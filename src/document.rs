@@ -0,0 +1,314 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::Block;
+use crate::BoardError;
+
+// Version written by this build. Bump it together with a new step in `migrate`
+pub const BOARD_VERSION: u64 = 11;
+
+// Board file as stored on disk
+#[derive(Serialize, Deserialize)]
+pub struct BoardDocument {
+    pub version: u64,
+    #[serde(rename = "board-name")]
+    pub board_name: String,
+    #[serde(default)]
+    pub members: Vec<String>, // Everyone tasks can be assigned to
+    #[serde(rename = "next-id", default)]
+    pub next_id: u64, // ID given to the next new task or block
+    pub blocks: Vec<Block>,
+}
+
+impl BoardDocument {
+    pub fn new(board_name: String, members: Vec<String>, next_id: u64, blocks: Vec<Block>) -> Self {
+        Self {
+            version: BOARD_VERSION,
+            board_name,
+            members,
+            next_id,
+            blocks,
+        }
+    }
+
+    // First ID no task or block of the board uses, even when `next-id` was edited by hand
+    pub fn first_free_id(&self) -> u64 {
+        let taken = self
+            .blocks
+            .iter()
+            .flat_map(|block| {
+                std::iter::once(block.id).chain(block.task_list.iter().map(|task| task.id))
+            })
+            .map(|id| id + 1)
+            .max()
+            .unwrap_or(1);
+        self.next_id.max(taken)
+    }
+
+    pub fn to_json(&self) -> Result<String, BoardError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    // Reads a board file of any known version, upgrading it to the current one
+    pub fn from_json(contents: &str) -> Result<Self, BoardError> {
+        let data: Value = serde_json::from_str(contents)?;
+
+        let document: BoardDocument = serde_json::from_value(migrate(data)?)
+            .map_err(|e| BoardError::Schema(e.to_string()))?;
+
+        if document.blocks.is_empty() {
+            return Err(BoardError::Schema("the board has no blocks".to_string()));
+        }
+
+        Ok(document)
+    }
+}
+
+// Files written before the version field existed count as version 0 when their blocks are
+// the fixed todo/active/completed object, and as version 1 when they are a list
+fn version_of(data: &Value) -> Result<u64, BoardError> {
+    match &data["version"] {
+        Value::Null => match &data["blocks"] {
+            Value::Object(_) => Ok(0),
+            Value::Array(_) => Ok(1),
+            _ => Err(BoardError::Schema("missing \"blocks\"".to_string())),
+        },
+        version => version
+            .as_u64()
+            .ok_or_else(|| BoardError::Schema("\"version\" is not a number".to_string())),
+    }
+}
+
+fn migrate(mut data: Value) -> Result<Value, BoardError> {
+    if !data.is_object() {
+        return Err(BoardError::Schema("expected a JSON object".to_string()));
+    }
+
+    let mut version = version_of(&data)?;
+    if version > BOARD_VERSION {
+        return Err(BoardError::Schema(format!(
+            "version {} is newer than the supported version {}",
+            version, BOARD_VERSION
+        )));
+    }
+
+    while version < BOARD_VERSION {
+        data = match version {
+            0 => migrate_v0(data),
+            1 => migrate_v1(data),
+            2 => migrate_v2(data),
+            3 => migrate_v3(data),
+            4 => migrate_v4(data),
+            5 => migrate_v5(data),
+            6 => migrate_v6(data),
+            7 => migrate_v7(data),
+            8 => migrate_v8(data),
+            9 => migrate_v9(data),
+            10 => migrate_v10(data),
+            _ => unreachable!(),
+        };
+        version += 1;
+    }
+
+    Ok(data)
+}
+
+// v0 -> v1: the three fixed blocks become a list of titled blocks
+fn migrate_v0(mut data: Value) -> Value {
+    let blocks = data["blocks"].take();
+
+    data["blocks"] = Value::Array(
+        [
+            ("todo", "To Do"),
+            ("active", "Active"),
+            ("completed", "Completed"),
+        ]
+        .iter()
+        .map(|(key, title)| {
+            json!({
+                "title": title,
+                "tasks": blocks[*key].clone()
+            })
+        })
+        .collect(),
+    );
+
+    data
+}
+
+// v1 -> v2: adds the version field
+fn migrate_v1(mut data: Value) -> Value {
+    data["version"] = json!(2);
+    data
+}
+
+// v2 -> v3: tasks get a priority. Anything malformed is left for deserializing to report
+fn migrate_v2(mut data: Value) -> Value {
+    if let Some(blocks) = data["blocks"].as_array_mut() {
+        for block in blocks {
+            if let Some(tasks) = block.get_mut("tasks").and_then(Value::as_array_mut) {
+                for task in tasks.iter_mut().filter_map(Value::as_object_mut) {
+                    task.insert("priority".to_string(), json!("Normal"));
+                }
+            }
+        }
+    }
+    data["version"] = json!(3);
+    data
+}
+
+// v3 -> v4: tasks can have a due date, older tasks have none
+fn migrate_v3(mut data: Value) -> Value {
+    data["version"] = json!(4);
+    data
+}
+
+// v4 -> v5: tasks can have tags, older tasks have none
+fn migrate_v4(mut data: Value) -> Value {
+    data["version"] = json!(5);
+    data
+}
+
+// v5 -> v6: boards get a member roster and tasks assignees, older boards have neither
+fn migrate_v5(mut data: Value) -> Value {
+    data["version"] = json!(6);
+    data
+}
+
+// v6 -> v7: tasks can have a checklist, older tasks have none
+fn migrate_v6(mut data: Value) -> Value {
+    data["version"] = json!(7);
+    data
+}
+
+// v7 -> v8: tasks get an ID so they can link to each other, numbered in board order
+fn migrate_v7(mut data: Value) -> Value {
+    let mut next_id: u64 = 1;
+    if let Some(blocks) = data["blocks"].as_array_mut() {
+        for block in blocks {
+            if let Some(tasks) = block.get_mut("tasks").and_then(Value::as_array_mut) {
+                for task in tasks.iter_mut().filter_map(Value::as_object_mut) {
+                    task.insert("id".to_string(), json!(next_id));
+                    next_id += 1;
+                }
+            }
+        }
+    }
+    data["next-id"] = json!(next_id);
+    data["version"] = json!(8);
+    data
+}
+
+// v8 -> v9: blocks get an ID too, numbered after every task ID
+fn migrate_v8(mut data: Value) -> Value {
+    let mut next_id = data["next-id"].as_u64().unwrap_or(1);
+    if let Some(blocks) = data["blocks"].as_array_mut() {
+        let task_ids = blocks
+            .iter()
+            .filter_map(|block| block["tasks"].as_array())
+            .flatten()
+            .filter_map(|task| task["id"].as_u64());
+        if let Some(last) = task_ids.max() {
+            next_id = next_id.max(last + 1);
+        }
+
+        for block in blocks.iter_mut().filter_map(Value::as_object_mut) {
+            block.insert("id".to_string(), json!(next_id));
+            next_id += 1;
+        }
+    }
+    data["next-id"] = json!(next_id);
+    data["version"] = json!(9);
+    data
+}
+
+// v9 -> v10: tasks record when they were created and changed and where they have been,
+// nothing is known about that for older tasks
+fn migrate_v9(mut data: Value) -> Value {
+    data["version"] = json!(10);
+    data
+}
+
+// v10 -> v11: task histories also record priority, checklist and blocker changes
+fn migrate_v10(mut data: Value) -> Value {
+    data["version"] = json!(11);
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Priority;
+    use crate::TaskStatus;
+
+    // As saved before the version field, with the three fixed blocks
+    const V0: &str = r#"{
+        "board-name": "Old board",
+        "blocks": {
+            "todo": [{"status": "Todo", "title": "First", "description": ""}],
+            "active": [],
+            "completed": [{"status": "Completed", "title": "Second", "description": "Done"}]
+        }
+    }"#;
+
+    #[test]
+    fn v0_is_upgraded_to_the_current_version() {
+        let document = BoardDocument::from_json(V0).unwrap();
+
+        assert_eq!(document.version, BOARD_VERSION);
+        assert_eq!(document.board_name, "Old board");
+        let titles: Vec<&str> = document.blocks.iter().map(|b| b.title.as_str()).collect();
+        assert_eq!(titles, ["To Do", "Active", "Completed"]);
+
+        let first = &document.blocks[0].task_list[0];
+        assert_eq!(first.title, "First");
+        assert_eq!(first.priority, Priority::Normal);
+        assert!(first.history.is_empty());
+        let second = &document.blocks[2].task_list[0];
+        assert!(matches!(second.status, TaskStatus::Completed));
+        assert_eq!(second.description, "Done");
+    }
+
+    #[test]
+    fn v0_tasks_are_numbered_before_blocks() {
+        let document = BoardDocument::from_json(V0).unwrap();
+
+        assert_eq!(document.blocks[0].task_list[0].id, 1);
+        assert_eq!(document.blocks[2].task_list[0].id, 2);
+        let block_ids: Vec<u64> = document.blocks.iter().map(|b| b.id).collect();
+        assert_eq!(block_ids, [3, 4, 5]);
+        assert_eq!(document.next_id, 6);
+        assert_eq!(document.first_free_id(), 6);
+    }
+
+    #[test]
+    fn current_version_round_trips() {
+        let document = BoardDocument::from_json(V0).unwrap();
+        let again = BoardDocument::from_json(&document.to_json().unwrap()).unwrap();
+
+        assert_eq!(again.next_id, document.next_id);
+        assert_eq!(again.blocks.len(), 3);
+        assert_eq!(again.blocks[2].task_list[0].title, "Second");
+    }
+
+    #[test]
+    fn newer_and_broken_files_are_refused() {
+        let newer = format!(r#"{{"version": {}, "blocks": []}}"#, BOARD_VERSION + 1);
+        assert!(matches!(
+            BoardDocument::from_json(&newer),
+            Err(BoardError::Schema(_))
+        ));
+        assert!(matches!(
+            BoardDocument::from_json(r#"{"board-name": "x"}"#),
+            Err(BoardError::Schema(_))
+        ));
+        assert!(matches!(
+            BoardDocument::from_json("[1, 2]"),
+            Err(BoardError::Schema(_))
+        ));
+        assert!(matches!(
+            BoardDocument::from_json("{"),
+            Err(BoardError::Parse(_))
+        ));
+    }
+}
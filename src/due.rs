@@ -0,0 +1,131 @@
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+use crossterm::style::Color;
+
+use crate::Task;
+use crate::TaskStatus;
+
+// Tasks due within this many days are highlighted as due soon
+pub const DUE_SOON_DAYS: i64 = 2;
+
+#[derive(PartialEq)]
+pub enum Urgency {
+    Overdue,
+    DueSoon,
+    Later,
+}
+
+impl Urgency {
+    // `on_white` picks shades that read on a white background
+    pub fn color(&self, on_white: bool) -> Color {
+        match (self, on_white) {
+            (Urgency::Overdue, false) => Color::Red,
+            (Urgency::Overdue, true) => Color::DarkRed,
+            (Urgency::DueSoon, false) => Color::Yellow,
+            (Urgency::DueSoon, true) => Color::DarkYellow,
+            (Urgency::Later, false) => Color::Grey,
+            (Urgency::Later, true) => Color::Black,
+        }
+    }
+}
+
+// Tasks without a due date and finished tasks are never late
+pub fn urgency(task: &Task, today: NaiveDate) -> Urgency {
+    let due = match task.due {
+        Some(due) if !matches!(task.status, TaskStatus::Completed) => due,
+        _ => return Urgency::Later,
+    };
+
+    let days = (due - today).num_days();
+    if days < 0 {
+        Urgency::Overdue
+    } else if days <= DUE_SOON_DAYS {
+        Urgency::DueSoon
+    } else {
+        Urgency::Later
+    }
+}
+
+// Reads a due date written as YYYY-MM-DD, today, tomorrow, a weekday name (its next
+// occurrence after today) or an offset such as +3d, +2w or +1m
+pub fn parse_due(text: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let text = text.trim().to_lowercase();
+
+    match text.as_str() {
+        "today" => return Some(today),
+        "tomorrow" => return today.checked_add_days(Days::new(1)),
+        _ => {}
+    }
+
+    if let Some(offset) = text.strip_prefix('+') {
+        let (count, unit) = match offset.find(|c: char| !c.is_ascii_digit()) {
+            Some(i) => offset.split_at(i),
+            None => (offset, "d"),
+        };
+        let count: u32 = count.parse().ok()?;
+        return match unit {
+            "d" => today.checked_add_days(Days::new(count.into())),
+            "w" => today.checked_add_days(Days::new(u64::from(count) * 7)),
+            "m" => today.checked_add_months(Months::new(count)),
+            _ => None,
+        };
+    }
+
+    if let Ok(weekday) = text.parse::<Weekday>() {
+        let ahead =
+            (7 + weekday.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+        let ahead = if ahead == 0 { 7 } else { ahead };
+        return today.checked_add_days(Days::new(ahead.into()));
+    }
+
+    NaiveDate::parse_from_str(&text, "%Y-%m-%d").ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn relative_days_and_weeks() {
+        let today = date(2026, 10, 18);
+        assert_eq!(parse_due("+3d", today), Some(date(2026, 10, 21)));
+        assert_eq!(parse_due("+3", today), Some(date(2026, 10, 21)));
+        assert_eq!(parse_due("+2w", today), Some(date(2026, 11, 1)));
+        assert_eq!(parse_due(" Today ", today), Some(today));
+    }
+
+    #[test]
+    fn tomorrow_crosses_the_year() {
+        assert_eq!(
+            parse_due("tomorrow", date(2026, 12, 31)),
+            Some(date(2027, 1, 1))
+        );
+    }
+
+    #[test]
+    fn months_stop_at_the_end_of_shorter_months() {
+        assert_eq!(parse_due("+1m", date(2026, 1, 31)), Some(date(2026, 2, 28)));
+        assert_eq!(parse_due("+1m", date(2028, 1, 31)), Some(date(2028, 2, 29)));
+        assert_eq!(parse_due("+3d", date(2026, 2, 27)), Some(date(2026, 3, 2)));
+    }
+
+    #[test]
+    fn weekdays_are_always_ahead() {
+        // A Sunday, so "sun" is a week away rather than today
+        let today = date(2026, 10, 18);
+        assert_eq!(parse_due("mon", today), Some(date(2026, 10, 19)));
+        assert_eq!(parse_due("sun", today), Some(date(2026, 10, 25)));
+    }
+
+    #[test]
+    fn anything_else_is_rejected() {
+        let today = date(2026, 10, 18);
+        assert_eq!(parse_due("2026-02-30", today), None);
+        assert_eq!(parse_due("+3y", today), None);
+        assert_eq!(parse_due("+d", today), None);
+        assert_eq!(parse_due("soon", today), None);
+    }
+}
@@ -0,0 +1,34 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum BoardError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    Schema(String), // Valid JSON that doesn't describe a board
+    NoPath,         // Saving a board that has no file yet
+}
+
+impl fmt::Display for BoardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BoardError::Io(e) => write!(f, "{}", e),
+            BoardError::Parse(e) => write!(f, "invalid JSON: {}", e),
+            BoardError::Schema(e) => write!(f, "not a board file: {}", e),
+            BoardError::NoPath => write!(f, "the board has no file yet"),
+        }
+    }
+}
+
+impl std::error::Error for BoardError {}
+
+impl From<std::io::Error> for BoardError {
+    fn from(e: std::io::Error) -> Self {
+        BoardError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for BoardError {
+    fn from(e: serde_json::Error) -> Self {
+        BoardError::Parse(e)
+    }
+}
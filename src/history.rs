@@ -0,0 +1,52 @@
+use std::collections::VecDeque;
+
+use crate::Block;
+
+// Number of steps that can be undone
+const HISTORY_LIMIT: usize = 100;
+
+// State of the board before a mutation
+#[derive(Clone)]
+pub struct Snapshot {
+    pub board_name: String,
+    pub members: Vec<String>,
+    pub block_list: Vec<Block>,
+    pub block_id: u64,        // Selected block
+    pub task_id: Option<u64>, // Selected task, if any
+    pub revision: u64,
+}
+
+pub struct History {
+    undo_stack: VecDeque<Snapshot>,
+    redo_stack: Vec<Snapshot>,
+}
+
+impl History {
+    pub fn default() -> Self {
+        Self {
+            undo_stack: VecDeque::new(),
+            redo_stack: vec![],
+        }
+    }
+
+    // Stores the state from before a mutation, a new mutation drops anything that was undone
+    pub fn record(&mut self, snapshot: Snapshot) {
+        self.undo_stack.push_back(snapshot);
+        if self.undo_stack.len() > HISTORY_LIMIT {
+            self.undo_stack.pop_front();
+        }
+        self.redo_stack.clear();
+    }
+
+    pub fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let previous = self.undo_stack.pop_back()?;
+        self.redo_stack.push(current);
+        Some(previous)
+    }
+
+    pub fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let next = self.redo_stack.pop()?;
+        self.undo_stack.push_back(current);
+        Some(next)
+    }
+}
@@ -0,0 +1,259 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+#[derive(PartialEq)]
+pub enum EditorAction {
+    None,
+    Confirm,
+    Cancel,
+}
+
+// Single line text input with a cursor, the limit counts characters and not bytes
+pub struct LineEditor {
+    buffer: Vec<char>,
+    cursor: usize,
+    limit: usize,
+}
+
+impl LineEditor {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            buffer: vec![],
+            cursor: 0,
+            limit,
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.buffer.iter().collect()
+    }
+
+    // Replaces the contents, cutting them at the limit, and puts the cursor at the end
+    pub fn set_text(&mut self, text: &str) {
+        self.buffer = text.chars().take(self.limit).collect();
+        self.cursor = self.buffer.len();
+    }
+
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.cursor = 0;
+    }
+
+    pub fn insert(&mut self, chr: char) {
+        if self.buffer.len() < self.limit && !chr.is_control() {
+            self.buffer.insert(self.cursor, chr);
+            self.cursor += 1;
+        }
+    }
+
+    // Pasted text is flattened to a single line
+    pub fn insert_str(&mut self, text: &str) {
+        for chr in text.chars() {
+            match chr {
+                '\r' => {}
+                '\n' | '\t' => self.insert(' '),
+                _ => self.insert(chr),
+            }
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.buffer.remove(self.cursor);
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if self.cursor < self.buffer.len() {
+            self.buffer.remove(self.cursor);
+        }
+    }
+
+    pub fn move_left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.buffer.len());
+    }
+
+    pub fn home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.cursor = self.buffer.len();
+    }
+
+    fn word_start(&self) -> usize {
+        let mut i = self.cursor;
+        while i > 0 && self.buffer[i - 1].is_whitespace() {
+            i -= 1;
+        }
+        while i > 0 && !self.buffer[i - 1].is_whitespace() {
+            i -= 1;
+        }
+        i
+    }
+
+    fn word_end(&self) -> usize {
+        let mut i = self.cursor;
+        while i < self.buffer.len() && self.buffer[i].is_whitespace() {
+            i += 1;
+        }
+        while i < self.buffer.len() && !self.buffer[i].is_whitespace() {
+            i += 1;
+        }
+        i
+    }
+
+    pub fn move_word_left(&mut self) {
+        self.cursor = self.word_start();
+    }
+
+    pub fn move_word_right(&mut self) {
+        self.cursor = self.word_end();
+    }
+
+    // Removes the word behind the cursor
+    pub fn delete_word(&mut self) {
+        let start = self.word_start();
+        self.buffer.drain(start..self.cursor);
+        self.cursor = start;
+    }
+
+    // Removes everything behind the cursor
+    pub fn delete_to_start(&mut self) {
+        self.buffer.drain(..self.cursor);
+        self.cursor = 0;
+    }
+
+    // Returns the part of the text that fits in `width` columns while keeping the cursor on
+    // screen, together with the column the cursor sits in
+    pub fn visible(&self, width: u16) -> (String, u16) {
+        let width = width.max(1) as usize;
+
+        let mut start = 0;
+        let mut cursor_col: usize = self.buffer[..self.cursor]
+            .iter()
+            .map(|c| c.width().unwrap_or(0))
+            .sum();
+        while cursor_col >= width && start < self.cursor {
+            cursor_col -= self.buffer[start].width().unwrap_or(0);
+            start += 1;
+        }
+
+        let mut shown = String::new();
+        for chr in self.buffer[start..].iter() {
+            if shown.width() + chr.width().unwrap_or(0) > width {
+                break;
+            }
+            shown.push(*chr);
+        }
+
+        (shown, cursor_col as u16)
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> EditorAction {
+        if key.kind != KeyEventKind::Press {
+            return EditorAction::None;
+        }
+
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+
+        match key.code {
+            KeyCode::Enter => return EditorAction::Confirm,
+            KeyCode::Esc => return EditorAction::Cancel,
+
+            KeyCode::Char('w') if ctrl => self.delete_word(),
+            KeyCode::Char('u') if ctrl => self.delete_to_start(),
+            KeyCode::Char('a') if ctrl => self.home(),
+            KeyCode::Char('e') if ctrl => self.end(),
+            KeyCode::Char('b') if alt => self.move_word_left(),
+            KeyCode::Char('f') if alt => self.move_word_right(),
+            KeyCode::Char(chr) if !ctrl && !alt => self.insert(chr),
+
+            KeyCode::Backspace if ctrl || alt => self.delete_word(),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete => self.delete(),
+
+            KeyCode::Left if ctrl => self.move_word_left(),
+            KeyCode::Right if ctrl => self.move_word_right(),
+            KeyCode::Left => self.move_left(),
+            KeyCode::Right => self.move_right(),
+            KeyCode::Home => self.home(),
+            KeyCode::End => self.end(),
+
+            _ => {}
+        }
+
+        EditorAction::None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled(text: &str, limit: usize) -> LineEditor {
+        let mut editor = LineEditor::new(limit);
+        editor.set_text(text);
+        editor
+    }
+
+    #[test]
+    fn set_text_cuts_at_the_limit_in_chars() {
+        let editor = filled("héllo wörld", 5);
+        assert_eq!(editor.text(), "héllo");
+        assert_eq!(editor.cursor, 5);
+    }
+
+    #[test]
+    fn insert_stops_at_the_limit() {
+        let mut editor = filled("", 3);
+        for _ in 0..5 {
+            editor.insert('é');
+        }
+        assert_eq!(editor.text(), "ééé");
+
+        let mut editor = filled("", 10);
+        editor.insert_str("ab\ncd\r\tef");
+        assert_eq!(editor.text(), "ab cd ef");
+    }
+
+    #[test]
+    fn delete_word_removes_the_word_behind_the_cursor() {
+        let mut editor = filled("foo bar  ", 15);
+        editor.delete_word();
+        assert_eq!(editor.text(), "foo ");
+        editor.delete_word();
+        assert_eq!(editor.text(), "");
+
+        let mut editor = filled("foo bar", 15);
+        editor.cursor = 5;
+        editor.delete_word();
+        assert_eq!(editor.text(), "foo ar");
+        assert_eq!(editor.cursor, 4);
+    }
+
+    #[test]
+    fn visible_keeps_the_cursor_on_screen() {
+        let mut editor = filled("abcdefghij", 15);
+        assert_eq!(editor.visible(5), ("ghij".to_string(), 4));
+        editor.home();
+        assert_eq!(editor.visible(5), ("abcde".to_string(), 0));
+        assert_eq!(editor.visible(20), ("abcdefghij".to_string(), 0));
+    }
+
+    #[test]
+    fn visible_counts_wide_chars_by_columns() {
+        let editor = filled("日本語テキ", 15);
+        assert_eq!(editor.visible(6), ("テキ".to_string(), 4));
+    }
+}
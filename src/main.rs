@@ -10,15 +10,47 @@ use selector::*;
 mod task;
 use task::*;
 
+mod due;
+use due::*;
+
 mod block;
 use block::*;
 
+mod line_editor;
+use line_editor::*;
+
+mod text_editor;
+use text_editor::*;
+
 mod terminalw;
 use terminalw::*;
 
+mod history;
+use history::*;
+
+mod error;
+use error::*;
+
+mod config;
+use config::*;
+
+mod storage;
+use storage::*;
+
+mod document;
+use document::*;
+
+mod stats;
+use stats::*;
+
 mod board;
 use board::*;
 
 fn main() {
-    Board::default().run();
+    // The board, and with it the terminal session, is gone before anything gets printed
+    let result = Board::default().run();
+    if let Err(e) = result {
+        eprintln!("kanban-rs: could not load the board: {}", e);
+        std::process::exit(1);
+    }
 }
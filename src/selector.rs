@@ -1,15 +1,18 @@
 use crate::Position;
 
+// Pointer into the board by block and task ID, so it stays on the same task while tasks and
+// blocks are added, removed or reordered around it. The board works out the positions
 pub struct Selector {
-    pub block_ptr: u8,
-    pub task_ptr: u8,
+    pub block_id: u64,
+    pub task_id: Option<u64>, // None in a block without tasks
     pub position: Position,
 }
 impl Selector {
+    // Points at nothing until the board points it at a block, the first block stands in
     pub fn default() -> Self {
         Self {
-            block_ptr: 0,
-            task_ptr: 0,
+            block_id: 0,
+            task_id: None,
             position: Position { x: 0, y: 0 },
         }
     }
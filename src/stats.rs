@@ -0,0 +1,391 @@
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate};
+
+use crate::Block;
+use crate::Task;
+use crate::TaskEvent;
+use crate::TaskStatus;
+
+// Weeks of throughput shown, ending with the current one
+pub const THROUGHPUT_WEEKS: usize = 8;
+
+// Spread of a set of durations, in days
+pub struct Summary {
+    pub count: usize,
+    pub average: f64,
+    pub p50: f64,
+    pub p85: f64,
+    pub p95: f64,
+}
+
+impl Summary {
+    fn of(mut days: Vec<f64>) -> Option<Self> {
+        if days.is_empty() {
+            return None;
+        }
+        days.sort_by(f64::total_cmp);
+
+        // Nearest rank: the smallest value with at least p% of the values at or below it
+        let percentile = |p: usize| days[(p * days.len()).div_ceil(100).max(1) - 1];
+        Some(Self {
+            count: days.len(),
+            average: days.iter().sum::<f64>() / days.len() as f64,
+            p50: percentile(50),
+            p85: percentile(85),
+            p95: percentile(95),
+        })
+    }
+}
+
+// Figures for the whole board, worked out from the history of its tasks. Tasks completed
+// without a recorded move into the last block, such as tasks from before the history was
+// kept, count in the blocks but not in the times or the throughput
+pub struct Stats {
+    pub blocks: Vec<(String, usize)>,
+    pub cycle_time: Option<Summary>, // From first entering an in progress block to completion
+    pub lead_time: Option<Summary>,  // From creation to completion
+    pub throughput: Vec<(NaiveDate, usize)>, // Tasks completed in the week starting that Monday
+}
+
+impl Stats {
+    pub fn new(blocks: &[Block], today: NaiveDate) -> Self {
+        let mut cycle_days = vec![];
+        let mut lead_days = vec![];
+        let mut completions = vec![];
+
+        for task in blocks.iter().flat_map(|block| block.task_list.iter()) {
+            let completed = match completed_at(task, blocks) {
+                Some(completed) => completed,
+                None => continue,
+            };
+            completions.push(completed.date_naive());
+
+            if let Some(started) = started_at(task, blocks) {
+                cycle_days.push(days_between(started, completed));
+            }
+            if let Some(created) = task.created {
+                lead_days.push(days_between(created, completed));
+            }
+        }
+
+        let this_week = week_start(today);
+        let throughput = (0..THROUGHPUT_WEEKS as u64)
+            .rev()
+            .filter_map(|weeks_ago| this_week.checked_sub_days(Days::new(weeks_ago * 7)))
+            .map(|week| {
+                let count = completions
+                    .iter()
+                    .filter(|date| week_start(**date) == week)
+                    .count();
+                (week, count)
+            })
+            .collect();
+
+        Self {
+            blocks: blocks
+                .iter()
+                .map(|block| (block.title.clone(), block.task_list.len()))
+                .collect(),
+            cycle_time: Summary::of(cycle_days),
+            lead_time: Summary::of(lead_days),
+            throughput,
+        }
+    }
+
+    // Every figure as a section,name,value row
+    pub fn to_csv(&self) -> String {
+        let mut rows = vec!["section,name,value".to_string()];
+
+        for (title, count) in self.blocks.iter() {
+            rows.push(format!("tasks per block,{},{}", csv_field(title), count));
+        }
+        for (section, summary) in [
+            ("cycle time (days)", &self.cycle_time),
+            ("lead time (days)", &self.lead_time),
+        ] {
+            if let Some(summary) = summary {
+                rows.push(format!("{},tasks,{}", section, summary.count));
+                rows.push(format!("{},average,{:.2}", section, summary.average));
+                rows.push(format!("{},p50,{:.2}", section, summary.p50));
+                rows.push(format!("{},p85,{:.2}", section, summary.p85));
+                rows.push(format!("{},p95,{:.2}", section, summary.p95));
+            }
+        }
+        for (week, count) in self.throughput.iter() {
+            rows.push(format!(
+                "throughput per week,{},{}",
+                week.format("%Y-%m-%d"),
+                count
+            ));
+        }
+
+        rows.join("\n") + "\n"
+    }
+}
+
+// Block IDs a task went through with the time it got there, oldest first
+fn visits(task: &Task) -> impl Iterator<Item = (DateTime<Local>, u64)> + '_ {
+    task.history.iter().filter_map(|event| match event {
+        TaskEvent::Created { at, block } => Some((*at, *block)),
+        TaskEvent::Moved { at, to, .. } => Some((*at, *to)),
+        TaskEvent::Edited { .. }
+        | TaskEvent::Prioritized { .. }
+        | TaskEvent::ChecklistChanged { .. }
+        | TaskEvent::BlockersChanged { .. } => None,
+    })
+}
+
+// Status of a block by its place on the board today, blocks deleted since have none
+fn status_of(block_id: u64, blocks: &[Block]) -> Option<TaskStatus> {
+    blocks
+        .iter()
+        .position(|block| block.id == block_id)
+        .map(|index| TaskStatus::for_block(index, blocks.len()))
+}
+
+// When a completed task last got into the last block
+fn completed_at(task: &Task, blocks: &[Block]) -> Option<DateTime<Local>> {
+    if !matches!(task.status, TaskStatus::Completed) {
+        return None;
+    }
+    let (at, block) = visits(task).last()?;
+    matches!(status_of(block, blocks), Some(TaskStatus::Completed)).then_some(at)
+}
+
+// When work on a task first started
+fn started_at(task: &Task, blocks: &[Block]) -> Option<DateTime<Local>> {
+    visits(task)
+        .find(|(_, block)| matches!(status_of(*block, blocks), Some(TaskStatus::Active)))
+        .map(|(at, _)| at)
+}
+
+fn days_between(from: DateTime<Local>, to: DateTime<Local>) -> f64 {
+    (to - from).num_seconds().max(0) as f64 / 86400.0
+}
+
+// Monday of the week `date` falls in
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Days::new(date.weekday().num_days_from_monday().into())
+}
+
+// Quotes a field that holds a separator, a quote or a line break
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+// Tasks in each block at the end of every day from `from` to `to`, blocks in board order.
+// Tasks without a recorded history can't be placed in time and are left out
+pub fn flow(blocks: &[Block], from: NaiveDate, to: NaiveDate) -> Vec<Vec<usize>> {
+    let days: Vec<NaiveDate> = from.iter_days().take_while(|day| *day <= to).collect();
+    let mut counts = vec![vec![0; blocks.len()]; days.len()];
+
+    for task in blocks.iter().flat_map(|block| block.task_list.iter()) {
+        let visits: Vec<(NaiveDate, u64)> = visits(task)
+            .map(|(at, block)| (at.date_naive(), block))
+            .collect();
+
+        for (day, day_counts) in days.iter().zip(counts.iter_mut()) {
+            let block = visits
+                .iter()
+                .take_while(|(date, _)| date <= day)
+                .last()
+                .and_then(|(_, id)| blocks.iter().position(|block| block.id == *id));
+            if let Some(index) = block {
+                day_counts[index] += 1;
+            }
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::Priority;
+
+    fn date(m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, m, d).unwrap()
+    }
+
+    // Noon keeps whole days apart whatever the local time zone
+    fn at(m: u32, d: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, m, d, 12, 0, 0).unwrap()
+    }
+
+    fn created(d: u32, block: u64) -> TaskEvent {
+        TaskEvent::Created {
+            at: at(10, d),
+            block,
+        }
+    }
+
+    fn moved(d: u32, from: u64, to: u64) -> TaskEvent {
+        TaskEvent::Moved {
+            at: at(10, d),
+            from,
+            to,
+        }
+    }
+
+    fn task(status: TaskStatus, history: Vec<TaskEvent>) -> Task {
+        Task {
+            id: 0,
+            status,
+            priority: Priority::Normal,
+            title: String::new(),
+            description: String::new(),
+            due: None,
+            tags: vec![],
+            assignees: vec![],
+            checklist: vec![],
+            blocked_by: vec![],
+            created: history.first().map(TaskEvent::at),
+            updated: None,
+            history,
+        }
+    }
+
+    // To Do (1), Doing (2) and Done (3) with:
+    // - a task done in three days, four after it was created
+    // - a task reopened from Done and finished again
+    // - a task reopened from Done and still in progress
+    // - a finished task from before history was recorded
+    fn board() -> Vec<Block> {
+        let mut blocks = vec![
+            Block::new(1, "To Do".to_string()),
+            Block::new(2, "Doing".to_string()),
+            Block::new(3, "Done".to_string()),
+        ];
+        blocks[1].task_list.push(task(
+            TaskStatus::Active,
+            vec![
+                created(5, 1),
+                moved(6, 1, 2),
+                moved(7, 2, 3),
+                moved(13, 3, 2),
+            ],
+        ));
+        blocks[2].task_list.push(task(
+            TaskStatus::Completed,
+            vec![created(5, 1), moved(6, 1, 2), moved(9, 2, 3)],
+        ));
+        blocks[2].task_list.push(task(
+            TaskStatus::Completed,
+            vec![
+                created(5, 1),
+                moved(7, 1, 2),
+                moved(8, 2, 3),
+                moved(12, 3, 2),
+                moved(14, 2, 3),
+            ],
+        ));
+        blocks[2]
+            .task_list
+            .push(task(TaskStatus::Completed, vec![]));
+        blocks
+    }
+
+    #[test]
+    fn no_durations_no_summary() {
+        assert!(Summary::of(vec![]).is_none());
+    }
+
+    #[test]
+    fn single_duration() {
+        let summary = Summary::of(vec![2.5]).unwrap();
+        assert_eq!(summary.count, 1);
+        assert_eq!(summary.average, 2.5);
+        assert_eq!((summary.p50, summary.p85, summary.p95), (2.5, 2.5, 2.5));
+    }
+
+    #[test]
+    fn even_number_of_durations_takes_nearest_rank() {
+        let summary = Summary::of(vec![4.0, 1.0, 3.0, 2.0]).unwrap();
+        assert_eq!(summary.count, 4);
+        assert_eq!(summary.average, 2.5);
+        // No averaging between the middle two, the median is the second value
+        assert_eq!(summary.p50, 2.0);
+        assert_eq!(summary.p85, 4.0);
+        assert_eq!(summary.p95, 4.0);
+    }
+
+    #[test]
+    fn percentiles_of_twenty() {
+        let summary = Summary::of((1..=20).map(f64::from).collect()).unwrap();
+        assert_eq!(summary.p50, 10.0);
+        assert_eq!(summary.p85, 17.0);
+        assert_eq!(summary.p95, 19.0);
+    }
+
+    #[test]
+    fn blocks_count_every_task() {
+        let stats = Stats::new(&board(), date(10, 16));
+        let counts: Vec<usize> = stats.blocks.iter().map(|(_, count)| *count).collect();
+        assert_eq!(counts, [0, 1, 3]);
+    }
+
+    #[test]
+    fn reopened_tasks_count_from_their_last_completion() {
+        let stats = Stats::new(&board(), date(10, 16));
+
+        // 3 days and 7 days, from first starting to finally finishing
+        let cycle_time = stats.cycle_time.unwrap();
+        assert_eq!(cycle_time.count, 2);
+        assert_eq!(cycle_time.average, 5.0);
+        assert_eq!((cycle_time.p50, cycle_time.p95), (3.0, 7.0));
+
+        let lead_time = stats.lead_time.unwrap();
+        assert_eq!(lead_time.count, 2);
+        assert_eq!(lead_time.average, 6.5);
+        assert_eq!((lead_time.p50, lead_time.p95), (4.0, 9.0));
+    }
+
+    #[test]
+    fn throughput_by_week_ending_this_one() {
+        let stats = Stats::new(&board(), date(10, 16));
+
+        assert_eq!(stats.throughput.len(), THROUGHPUT_WEEKS);
+        assert_eq!(stats.throughput[THROUGHPUT_WEEKS - 1], (date(10, 12), 1));
+        assert_eq!(stats.throughput[THROUGHPUT_WEEKS - 2], (date(10, 5), 1));
+        assert_eq!(stats.throughput[0].0, date(8, 24));
+        let total: usize = stats.throughput.iter().map(|(_, count)| count).sum();
+        assert_eq!(total, 2);
+    }
+
+    #[test]
+    fn nothing_completed_nothing_measured() {
+        let stats = Stats::new(&board()[..2], date(10, 16));
+        assert!(stats.cycle_time.is_none());
+        assert!(stats.lead_time.is_none());
+        assert!(stats.throughput.iter().all(|(_, count)| *count == 0));
+    }
+
+    #[test]
+    fn csv_quotes_block_titles() {
+        let mut blocks = board();
+        blocks[0].title = "To Do, \"soon\"".to_string();
+        let csv = Stats::new(&blocks, date(10, 16)).to_csv();
+
+        assert!(csv.starts_with("section,name,value\n"));
+        assert!(csv.contains("tasks per block,\"To Do, \"\"soon\"\"\",0\n"));
+        assert!(csv.contains("cycle time (days),p50,3.00\n"));
+        assert!(csv.contains("throughput per week,2026-10-12,1\n"));
+    }
+
+    #[test]
+    fn flow_places_tasks_by_day() {
+        let counts = flow(&board(), date(10, 4), date(10, 14));
+
+        assert_eq!(counts.len(), 11);
+        assert_eq!(counts[0], [0, 0, 0]);
+        assert_eq!(counts[1], [3, 0, 0]);
+        assert_eq!(counts[3], [0, 2, 1]);
+        assert_eq!(counts[9], [0, 2, 1]);
+        assert_eq!(counts[10], [0, 1, 2]);
+    }
+}
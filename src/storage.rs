@@ -0,0 +1,209 @@
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// `board.json` -> `board.json.<n>`
+pub fn backup_path(path: &Path, n: usize) -> PathBuf {
+    let mut name: OsString = path.as_os_str().to_owned();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+// The file a symlinked board points to, saving replaces that file and keeps the link
+fn resolve(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+// Shifts `board.json.1` to `.2` and so on, dropping the oldest, then keeps the current file
+// as `.1`. The current file stays in place so there is always a board on disk. Backups past
+// the limit, left over from a higher setting, are removed
+fn rotate_backups(path: &Path, backups: usize) -> std::io::Result<()> {
+    if backups == 0 || !path.exists() {
+        return Ok(());
+    }
+
+    let mut n = backups + 1;
+    while backup_path(path, n).exists() {
+        fs::remove_file(backup_path(path, n))?;
+        n += 1;
+    }
+
+    for n in (1..backups).rev() {
+        let from = backup_path(path, n);
+        if from.exists() {
+            fs::rename(&from, backup_path(path, n + 1))?;
+        }
+    }
+
+    let first = backup_path(path, 1);
+    if first.exists() {
+        fs::remove_file(&first)?;
+    }
+    // A hard link keeps the modification time of the saved board, copying is the fallback
+    if fs::hard_link(path, &first).is_err() {
+        fs::copy(path, &first)?;
+    }
+
+    Ok(())
+}
+
+// Writes `contents` to a temporary file next to `path`, syncs it and renames it over `path`,
+// so a crash leaves either the old or the new board but never a partial one
+pub fn write_atomic(path: &Path, contents: &[u8], backups: usize) -> std::io::Result<()> {
+    let path = &resolve(path);
+    let tmp = temp_path(path);
+
+    let result = (|| {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&tmp)?;
+        // The new file takes the permissions of the one it replaces before anything is in it
+        if let Ok(metadata) = fs::metadata(path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+        file.write_all(contents)?;
+        file.sync_all()?;
+
+        rotate_backups(path, backups)?;
+        fs::rename(&tmp, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+        return result;
+    }
+
+    // Make the rename itself durable
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        let dir = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+        if let Ok(dir) = File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+
+    Ok(())
+}
+
+// Existing backups of `path`, newest first, with the time each one was saved
+pub fn list_backups(path: &Path, backups: usize) -> Vec<(PathBuf, SystemTime)> {
+    let path = &resolve(path);
+    (1..=backups)
+        .map(|n| backup_path(path, n))
+        .filter_map(|p| {
+            let modified = fs::metadata(&p).and_then(|m| m.modified()).ok()?;
+            Some((p, modified))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An empty directory of its own for each test
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("kanban-rs-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        // Backups are listed by their resolved path
+        fs::canonicalize(dir).unwrap()
+    }
+
+    fn read(path: &Path) -> String {
+        fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn backups_rotate_newest_first() {
+        let dir = scratch("rotate");
+        let board = dir.join("board.json");
+        for contents in ["1", "2", "3", "4"] {
+            write_atomic(&board, contents.as_bytes(), 2).unwrap();
+        }
+
+        assert_eq!(read(&board), "4");
+        assert_eq!(read(&backup_path(&board, 1)), "3");
+        assert_eq!(read(&backup_path(&board, 2)), "2");
+        assert!(!backup_path(&board, 3).exists());
+        assert!(!temp_path(&board).exists());
+
+        let listed: Vec<PathBuf> = list_backups(&board, 2).into_iter().map(|b| b.0).collect();
+        assert_eq!(listed, [backup_path(&board, 1), backup_path(&board, 2)]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn lowering_the_limit_prunes_old_backups() {
+        let dir = scratch("prune");
+        let board = dir.join("board.json");
+        for contents in ["1", "2", "3", "4"] {
+            write_atomic(&board, contents.as_bytes(), 3).unwrap();
+        }
+        assert!(backup_path(&board, 3).exists());
+
+        write_atomic(&board, b"5", 1).unwrap();
+        assert_eq!(read(&board), "5");
+        assert_eq!(read(&backup_path(&board, 1)), "4");
+        assert!(!backup_path(&board, 2).exists());
+        assert!(!backup_path(&board, 3).exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn saving_keeps_the_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = scratch("permissions");
+        let board = dir.join("board.json");
+        fs::write(&board, "1").unwrap();
+        fs::set_permissions(&board, fs::Permissions::from_mode(0o600)).unwrap();
+
+        write_atomic(&board, b"2", 1).unwrap();
+        let mode = fs::metadata(&board).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_symlinked_board_stays_a_link() {
+        let dir = scratch("symlink");
+        let real = dir.join("real.json");
+        let link = dir.join("link.json");
+        fs::write(&real, "1").unwrap();
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        write_atomic(&link, b"2", 1).unwrap();
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(read(&real), "2");
+        // Backups go next to the file the link points to
+        assert_eq!(read(&backup_path(&real, 1)), "1");
+        assert!(!backup_path(&link, 1).exists());
+        assert_eq!(list_backups(&link, 1).len(), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
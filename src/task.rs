@@ -1,3 +1,5 @@
+use chrono::{DateTime, Local, NaiveDate};
+use crossterm::style::Color;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -7,9 +9,208 @@ pub enum TaskStatus {
     Completed,
 }
 
+impl TaskStatus {
+    // First block holds new work, last block holds finished work, anything between is in
+    // progress
+    pub fn for_block(index: usize, blocks: usize) -> Self {
+        if index == 0 {
+            TaskStatus::Todo
+        } else if index + 1 == blocks {
+            TaskStatus::Completed
+        } else {
+            TaskStatus::Active
+        }
+    }
+}
+
+// Ordered from least to most pressing, so sorting puts Urgent last
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Priority {
+    Low,
+    Normal,
+    High,
+    Urgent,
+}
+
+impl Priority {
+    pub fn name(&self) -> &str {
+        match self {
+            Priority::Low => "Low",
+            Priority::Normal => "Normal",
+            Priority::High => "High",
+            Priority::Urgent => "Urgent",
+        }
+    }
+
+    // Colour of the marker drawn next to the task title
+    pub fn color(&self) -> Color {
+        match self {
+            Priority::Low => Color::Blue,
+            Priority::Normal => Color::Grey,
+            Priority::High => Color::Yellow,
+            Priority::Urgent => Color::Red,
+        }
+    }
+
+    pub fn raised(&self) -> Self {
+        match self {
+            Priority::Low => Priority::Normal,
+            Priority::Normal => Priority::High,
+            Priority::High | Priority::Urgent => Priority::Urgent,
+        }
+    }
+
+    pub fn lowered(&self) -> Self {
+        match self {
+            Priority::Low | Priority::Normal => Priority::Low,
+            Priority::High => Priority::Normal,
+            Priority::Urgent => Priority::High,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChecklistItem {
+    pub text: String,
+    #[serde(default)]
+    pub done: bool,
+}
+
+// Something that happened to a task, kept in its history. Blocks are referred to by ID so
+// renaming or moving them doesn't rewrite the past
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum TaskEvent {
+    Created {
+        at: DateTime<Local>,
+        block: u64,
+    },
+    Moved {
+        at: DateTime<Local>,
+        from: u64,
+        to: u64,
+    },
+    Edited {
+        at: DateTime<Local>,
+    },
+    Prioritized {
+        at: DateTime<Local>,
+        from: Priority,
+        to: Priority,
+    },
+    // Progress after an item was added, rewritten, ticked or deleted. Reordering items changes
+    // nothing worth keeping and only marks the task as updated
+    ChecklistChanged {
+        at: DateTime<Local>,
+        done: usize,
+        total: usize,
+    },
+    BlockersChanged {
+        at: DateTime<Local>,
+        #[serde(rename = "blocked-by")]
+        blocked_by: Vec<u64>,
+    },
+}
+
+impl TaskEvent {
+    pub fn at(&self) -> DateTime<Local> {
+        match self {
+            TaskEvent::Created { at, .. }
+            | TaskEvent::Moved { at, .. }
+            | TaskEvent::Edited { at }
+            | TaskEvent::Prioritized { at, .. }
+            | TaskEvent::ChecklistChanged { at, .. }
+            | TaskEvent::BlockersChanged { at, .. } => *at,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
+    pub id: u64, // Unique on its board and never reused, shared with block IDs
     pub status: TaskStatus,
+    pub priority: Priority,
     pub title: String,
     pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assignees: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checklist: Vec<ChecklistItem>,
+    // IDs of the tasks that have to be completed before this one
+    #[serde(default, rename = "blocked-by", skip_serializing_if = "Vec::is_empty")]
+    pub blocked_by: Vec<u64>,
+    // Tasks from before these were recorded have no times and no history
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<DateTime<Local>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated: Option<DateTime<Local>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<TaskEvent>,
+}
+
+impl Task {
+    // Marks the task as changed now
+    pub fn touch(&mut self) {
+        self.updated = Some(Local::now());
+    }
+
+    // Whether the parts of the task set through the edit prompts are the same in both
+    pub fn same_details(&self, other: &Task) -> bool {
+        self.title == other.title
+            && self.description == other.description
+            && self.priority == other.priority
+            && self.due == other.due
+            && self.tags == other.tags
+            && self.assignees == other.assignees
+    }
+
+    pub fn record(&mut self, event: TaskEvent) {
+        self.updated = Some(event.at());
+        self.history.push(event);
+    }
+
+    // Items done and items in the checklist, None when the task has no checklist
+    pub fn progress(&self) -> Option<(usize, usize)> {
+        if self.checklist.is_empty() {
+            return None;
+        }
+        let done = self.checklist.iter().filter(|item| item.done).count();
+        Some((done, self.checklist.len()))
+    }
+}
+
+// Splits a list of words (tags, member names) written with spaces or commas between them,
+// dropping repeats
+pub fn parse_list(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = vec![];
+    for tag in text.split(|c: char| c.is_whitespace() || c == ',') {
+        if !tag.is_empty() && !tags.iter().any(|t| t == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+// Two letter badge for a member: the first letters of the first two parts of the name, or its
+// first two letters when it is a single word
+pub fn initials(name: &str) -> String {
+    let parts: Vec<&str> = name
+        .split(|c: char| c.is_whitespace() || c == '.' || c == '-' || c == '_')
+        .filter(|part| !part.is_empty())
+        .collect();
+
+    let letters: String = match parts.as_slice() {
+        [] => String::new(),
+        [word] => word.chars().take(2).collect(),
+        [first, second, ..] => first
+            .chars()
+            .take(1)
+            .chain(second.chars().take(1))
+            .collect(),
+    };
+    letters.to_uppercase()
 }
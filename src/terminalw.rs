@@ -1,81 +1,197 @@
 use std::io::{stdout, Stdout, Write};
+use std::panic;
+use std::time::Duration;
 
 use crossterm::{
-    cursor, execute,
+    cursor,
+    event::{DisableBracketedPaste, DisableFocusChange, EnableBracketedPaste, EnableFocusChange},
+    execute, queue,
     style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor},
-    terminal::{enable_raw_mode, size, Clear, ClearType, SetSize, SetTitle},
+    terminal::{
+        disable_raw_mode, enable_raw_mode, size, Clear, ClearType, EnterAlternateScreen,
+        LeaveAlternateScreen, SetTitle,
+    },
 };
 
+use unicode_width::UnicodeWidthChar;
+
+use crate::Position;
 use crate::Size;
 
+// Second half of a double width character, it is covered by the first half and never printed
+const WIDE_TAIL: char = '\0';
+
+#[derive(Clone, Copy, PartialEq)]
+struct Cell {
+    chr: char,
+    fg: Color,
+    bg: Color,
+}
+
+const BLANK: Cell = Cell {
+    chr: ' ',
+    fg: Color::Reset,
+    bg: Color::Reset,
+};
+
+// Puts the terminal back the way the shell expects it. Also used from the panic hook, where
+// there is no TerminalW to go through
+fn restore_terminal() {
+    let _ = execute!(
+        stdout(),
+        ResetColor,
+        DisableBracketedPaste,
+        DisableFocusChange,
+        cursor::SetCursorStyle::DefaultUserShape,
+        cursor::Show,
+        LeaveAlternateScreen
+    );
+    let _ = disable_raw_mode();
+}
+
+// Owns the terminal session: the alternate screen and raw mode last as long as this does.
+// Drawing goes into a back buffer, present() then sends only the cells that changed
 pub struct TerminalW {
     size: Size,
     hook: Stdout,
+    back: Vec<Cell>,       // Frame being drawn
+    front: Vec<Cell>,      // Frame on screen
+    full_redraw: bool,     // Screen contents are unknown, clear and send everything
+    cursor: Position,      // Where the cursor is left after present()
+    title: Option<String>, // Last title sent, to skip sending it again
+    #[cfg(unix)]
+    tty: Option<std::fs::File>, // Watched for input and hangups, see wait_for_input
 }
 
 impl TerminalW {
     pub fn default() -> Result<Self, std::io::Error> {
-        enable_raw_mode();
         let size = size()?;
-        Ok(Self {
-            size: Size {
-                width: size.0,
-                height: size.1.saturating_sub(2),
-            },
-            hook: stdout(),
-        })
+
+        // A panic message printed over a raw mode alternate screen would be lost
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            restore_terminal();
+            default_hook(info);
+        }));
+
+        enable_raw_mode()?;
+        let mut hook = stdout();
+        execute!(
+            hook,
+            EnterAlternateScreen,
+            EnableBracketedPaste,
+            EnableFocusChange
+        )?;
+
+        let mut term = Self {
+            size: Size::default(),
+            hook,
+            back: vec![],
+            front: vec![],
+            full_redraw: true,
+            cursor: Position::default(),
+            title: None,
+            #[cfg(unix)]
+            tty: std::fs::File::open("/dev/tty").ok(),
+        };
+        term.resize(size.0, size.1);
+
+        Ok(term)
+    }
+
+    // Follows a change in the terminal size, the next present() redraws everything
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.size = Size { width, height };
+        self.back = vec![BLANK; width as usize * height as usize];
+        self.front = self.back.clone();
+        self.full_redraw = true;
     }
 
     pub fn set_title(&mut self, title: &str) {
-        execute!(self.hook, SetTitle(title));
+        if self.title.as_deref() != Some(title) {
+            let _ = execute!(self.hook, SetTitle(title));
+            self.title = Some(title.to_string());
+        }
     }
 
+    // Blanks the back buffer, frames are drawn from scratch
     pub fn clear_screen(&mut self) {
-        execute!(self.hook, Clear(ClearType::All));
+        self.back.fill(BLANK);
     }
 
-    pub fn clear_current_line(&mut self) {
-        execute!(self.hook, Clear(ClearType::CurrentLine));
+    pub fn clear_line(&mut self, y: u16) {
+        for x in 0..self.size.width {
+            self.put_cell(x, y, BLANK);
+        }
     }
 
-    pub fn cursor_hide(&mut self) {
-        execute!(self.hook, cursor::Hide);
+    pub fn set_cursor_shape(&mut self, cursor_shape: cursor::SetCursorStyle) {
+        let _ = execute!(self.hook, cursor_shape);
     }
 
-    pub fn cursor_show(&mut self) {
-        execute!(self.hook, cursor::Show);
+    pub fn move_cursor(&mut self, x: u16, y: u16) {
+        self.cursor = Position { x, y };
     }
 
-    pub fn set_cursor_shape(&mut self, cursor_shape: cursor::SetCursorStyle) {
-        execute!(self.hook, cursor_shape);
+    // Writes one cell, anything outside the screen is dropped. Overwriting either half of a
+    // double width character blanks the other half so the two never get out of step
+    fn put_cell(&mut self, x: u16, y: u16, cell: Cell) {
+        if x >= self.size.width || y >= self.size.height {
+            return;
+        }
+        let width = self.size.width as usize;
+        let i = y as usize * width + x as usize;
+
+        if self.back[i].chr == WIDE_TAIL && x > 0 {
+            self.back[i - 1] = BLANK;
+        }
+        if x + 1 < self.size.width && self.back[i + 1].chr == WIDE_TAIL && cell.chr != WIDE_TAIL {
+            self.back[i + 1] = BLANK;
+        }
+        self.back[i] = cell;
     }
 
-    pub fn move_cursor(&mut self, x: u16, y: u16) {
-        execute!(self.hook, cursor::MoveTo(x, y));
+    // Returns the column after the glyph
+    fn put_char(&mut self, chr: char, x: u16, y: u16, fg: Color, bg: Color) -> u16 {
+        match chr.width().unwrap_or(0) {
+            0 => x,
+            1 => {
+                self.put_cell(x, y, Cell { chr, fg, bg });
+                x + 1
+            }
+            _ => {
+                // Half a character can't be shown, a space takes its place at the right edge
+                if x + 1 >= self.size.width {
+                    self.put_cell(x, y, Cell { chr: ' ', fg, bg });
+                } else {
+                    self.put_cell(x, y, Cell { chr, fg, bg });
+                    self.put_cell(
+                        x + 1,
+                        y,
+                        Cell {
+                            chr: WIDE_TAIL,
+                            fg,
+                            bg,
+                        },
+                    );
+                }
+                x + 2
+            }
+        }
     }
 
     pub fn put_glyph(&mut self, chr: char, x: u16, y: u16, fg: Color, bg: Color) {
-        self.move_cursor(x, y);
-
-        execute!(
-            self.hook,
-            SetForegroundColor(fg),
-            SetBackgroundColor(bg),
-            Print(chr),
-            ResetColor
-        );
+        self.put_char(chr, x, y, fg, bg);
     }
 
     pub fn put_str(&mut self, line_str: String, x: u16, y: u16, fg: Color, bg: Color) {
-        self.move_cursor(x, y);
-
-        execute!(
-            self.hook,
-            SetForegroundColor(fg),
-            SetBackgroundColor(bg),
-            Print(line_str.to_string()),
-            ResetColor
-        );
+        let mut x = x;
+        for chr in line_str.chars() {
+            if x >= self.size.width {
+                break;
+            }
+            x = self.put_char(chr, x, y, fg, bg);
+        }
     }
 
     pub fn draw_rect(
@@ -117,31 +233,106 @@ impl TerminalW {
         );
 
         //Fill
-        match fill_color {
-            Some(c) => {
-                for px in x + 2..x + w - 1 {
-                    for py in y + 2..y + h - 1 {
-                        self.put_glyph(' ', px, py, c, c)
-                    }
+        if let Some(c) = fill_color {
+            for px in x + 1..x + w {
+                for py in y + 1..y + h {
+                    self.put_glyph(' ', px, py, c, c)
                 }
             }
-            None => {}
         }
     }
 
-    pub fn set_size(&mut self, cols: u16, rows: u16) {
-        execute!(self.hook, SetSize(cols, rows));
-        self.size = Size {
-            width: cols,
-            height: rows,
+    // Waits up to `timeout` for input, returns false once the terminal is gone. crossterm keeps
+    // reading a hung up terminal without ever returning, so it is only asked for events after
+    // this saw input
+    #[cfg(unix)]
+    pub fn wait_for_input(&self, timeout: Duration) -> bool {
+        use std::os::unix::io::AsRawFd;
+
+        let tty = match &self.tty {
+            Some(tty) => tty,
+            None => return crossterm::event::poll(timeout).is_ok(),
         };
+        let mut fd = libc::pollfd {
+            fd: tty.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout = timeout.as_millis().min(libc::c_int::MAX as u128) as libc::c_int;
+        // SAFETY: `fd` is a single initialised pollfd that outlives the call, matching the count
+        // of 1, and poll only writes to its `revents`
+        let ready = unsafe { libc::poll(&mut fd, 1, timeout) };
+        // An interrupted wait, such as by a signal, returns early with nothing to report. Any
+        // other failure means the terminal can't be waited on anymore
+        if ready < 0 {
+            return std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted;
+        }
+        fd.revents & (libc::POLLHUP | libc::POLLERR | libc::POLLNVAL) == 0
+    }
+
+    #[cfg(not(unix))]
+    pub fn wait_for_input(&self, timeout: Duration) -> bool {
+        crossterm::event::poll(timeout).is_ok()
     }
 
     pub fn get_size(&self) -> &Size {
         &self.size
     }
 
-    pub fn flush(&mut self) -> Result<(), std::io::Error> {
-        self.hook.flush()
+    // Sends the cells that differ from the last frame in a single write and leaves the cursor
+    // where move_cursor put it
+    pub fn present(&mut self) -> Result<(), std::io::Error> {
+        queue!(self.hook, cursor::Hide)?;
+
+        if self.full_redraw {
+            queue!(self.hook, ResetColor, Clear(ClearType::All))?;
+            self.front.fill(BLANK);
+            self.full_redraw = false;
+        }
+
+        let width = self.size.width as usize;
+        let mut fg = None;
+        let mut bg = None;
+        // Where the terminal cursor ended up after the last print
+        let mut at: Option<(u16, u16)> = None;
+
+        for (i, cell) in self.back.iter().enumerate() {
+            if *cell == self.front[i] || cell.chr == WIDE_TAIL {
+                continue;
+            }
+            let x = (i % width) as u16;
+            let y = (i / width) as u16;
+
+            if at != Some((x, y)) {
+                queue!(self.hook, cursor::MoveTo(x, y))?;
+            }
+            if fg != Some(cell.fg) {
+                queue!(self.hook, SetForegroundColor(cell.fg))?;
+                fg = Some(cell.fg);
+            }
+            if bg != Some(cell.bg) {
+                queue!(self.hook, SetBackgroundColor(cell.bg))?;
+                bg = Some(cell.bg);
+            }
+            queue!(self.hook, Print(cell.chr))?;
+            at = Some((x + cell.chr.width().unwrap_or(1) as u16, y));
+        }
+
+        queue!(
+            self.hook,
+            ResetColor,
+            cursor::MoveTo(self.cursor.x, self.cursor.y),
+            cursor::Show
+        )?;
+        self.hook.flush()?;
+
+        self.front.clone_from(&self.back);
+        Ok(())
+    }
+}
+
+impl Drop for TerminalW {
+    fn drop(&mut self) {
+        restore_terminal();
     }
 }
@@ -0,0 +1,369 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use unicode_width::UnicodeWidthChar;
+
+use crate::EditorAction;
+
+// Splits `chars` into rows no wider than `width` columns, breaking after whitespace when a
+// word doesn't fit and inside the word only when it is wider than a whole row.
+// Each row is returned as a [start, end) range so rows can be mapped back to the buffer.
+fn wrap_ranges(chars: &[char], width: usize) -> Vec<(usize, usize)> {
+    let width = width.max(1);
+
+    let mut ranges = vec![];
+    let mut start = 0;
+    let mut col = 0;
+    let mut last_break: Option<usize> = None;
+
+    let mut i = 0;
+    while i < chars.len() {
+        let chr = chars[i];
+
+        if chr == '\n' {
+            ranges.push((start, i));
+            start = i + 1;
+            col = 0;
+            last_break = None;
+            i += 1;
+            continue;
+        }
+
+        let w = chr.width().unwrap_or(0);
+
+        // Whitespace never pushes a row over, it hangs at the end instead
+        if chr.is_whitespace() {
+            col += w;
+            last_break = Some(i + 1);
+            i += 1;
+            continue;
+        }
+
+        if col + w > width && i > start {
+            let brk = match last_break {
+                Some(b) if b > start => b,
+                _ => i,
+            };
+            ranges.push((start, brk));
+            start = brk;
+            col = chars[start..i].iter().map(|c| c.width().unwrap_or(0)).sum();
+            last_break = None;
+            continue;
+        }
+
+        col += w;
+        i += 1;
+    }
+    ranges.push((start, chars.len()));
+
+    ranges
+}
+
+fn fit_width(chars: &[char], width: usize) -> String {
+    let mut s = String::new();
+    let mut col = 0;
+    for chr in chars {
+        if *chr == '\n' {
+            break;
+        }
+        let w = chr.width().unwrap_or(0);
+        if col + w > width {
+            break;
+        }
+        col += w;
+        s.push(*chr);
+    }
+    s
+}
+
+// Word wraps `text` to rows of at most `width` columns
+pub fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    wrap_ranges(&chars, width)
+        .iter()
+        .map(|(start, end)| fit_width(&chars[*start..*end], width))
+        .collect()
+}
+
+// Multi line text input that word wraps at a given width and scrolls vertically
+pub struct TextEditor {
+    buffer: Vec<char>,
+    cursor: usize,
+    scroll: usize,
+    width: usize,
+}
+
+impl TextEditor {
+    pub fn new() -> Self {
+        Self {
+            buffer: vec![],
+            cursor: 0,
+            scroll: 0,
+            width: 1,
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.buffer.iter().collect()
+    }
+
+    pub fn set_text(&mut self, text: &str) {
+        self.buffer = text.chars().filter(|c| *c != '\r').collect();
+        self.cursor = self.buffer.len();
+        self.scroll = 0;
+    }
+
+    pub fn clear(&mut self) {
+        self.set_text("");
+    }
+
+    pub fn insert(&mut self, chr: char) {
+        if chr == '\n' || !chr.is_control() {
+            self.buffer.insert(self.cursor, chr);
+            self.cursor += 1;
+        }
+    }
+
+    pub fn insert_str(&mut self, text: &str) {
+        for chr in text.chars() {
+            match chr {
+                '\r' => {}
+                '\t' => self.insert(' '),
+                _ => self.insert(chr),
+            }
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.buffer.remove(self.cursor);
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if self.cursor < self.buffer.len() {
+            self.buffer.remove(self.cursor);
+        }
+    }
+
+    // Removes the word behind the cursor, stopping at line breaks
+    pub fn delete_word(&mut self) {
+        let mut start = self.cursor;
+        while start > 0 && self.buffer[start - 1] == ' ' {
+            start -= 1;
+        }
+        while start > 0 && !self.buffer[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        if start == self.cursor {
+            start = start.saturating_sub(1);
+        }
+        self.buffer.drain(start..self.cursor);
+        self.cursor = start;
+    }
+
+    fn rows(&self) -> Vec<(usize, usize)> {
+        wrap_ranges(&self.buffer, self.width)
+    }
+
+    // Row the cursor is on. A cursor sitting on a soft break belongs to the next row
+    fn cursor_row(&self, rows: &[(usize, usize)]) -> usize {
+        rows.iter()
+            .rposition(|(start, _)| *start <= self.cursor)
+            .unwrap_or(0)
+    }
+
+    fn col_of(&self, start: usize, index: usize) -> usize {
+        self.buffer[start..index]
+            .iter()
+            .map(|c| c.width().unwrap_or(0))
+            .sum()
+    }
+
+    // Index in `row` that is closest to display column `col`
+    fn index_at(&self, row: (usize, usize), col: usize) -> usize {
+        let mut index = row.0;
+        let mut c = 0;
+        while index < row.1 {
+            let w = self.buffer[index].width().unwrap_or(0);
+            if c + w > col {
+                break;
+            }
+            c += w;
+            index += 1;
+        }
+        index
+    }
+
+    fn move_vertical(&mut self, down: bool, count: usize) {
+        let rows = self.rows();
+        let row = self.cursor_row(&rows);
+        let col = self.col_of(rows[row].0, self.cursor);
+
+        let target = if down {
+            (row + count).min(rows.len() - 1)
+        } else {
+            row.saturating_sub(count)
+        };
+
+        if target != row {
+            self.cursor = self.index_at(rows[target], col);
+        }
+    }
+
+    fn row_home(&mut self) {
+        let rows = self.rows();
+        self.cursor = rows[self.cursor_row(&rows)].0;
+    }
+
+    fn row_end(&mut self) {
+        let rows = self.rows();
+        let (start, end) = rows[self.cursor_row(&rows)];
+        // Stay before the space a soft break hangs on so the cursor keeps to its row
+        let mut end = end;
+        if end > start
+            && end < self.buffer.len()
+            && self.buffer[end] != '\n'
+            && self.buffer[end - 1].is_whitespace()
+        {
+            end -= 1;
+        }
+        self.cursor = end;
+    }
+
+    // Rows visible in a `width` x `height` area plus the cursor position inside it
+    pub fn view(&mut self, width: u16, height: u16) -> (Vec<String>, u16, u16) {
+        self.width = width.max(1) as usize;
+        let height = height.max(1) as usize;
+
+        let rows = self.rows();
+        let row = self.cursor_row(&rows);
+
+        if row < self.scroll {
+            self.scroll = row;
+        } else if row >= self.scroll + height {
+            self.scroll = row + 1 - height;
+        }
+
+        let lines = rows
+            .iter()
+            .skip(self.scroll)
+            .take(height)
+            .map(|(start, end)| fit_width(&self.buffer[*start..*end], self.width))
+            .collect();
+
+        let col = self.col_of(rows[row].0, self.cursor).min(self.width - 1);
+
+        (lines, col as u16, (row - self.scroll) as u16)
+    }
+
+    // Enter starts a new line, Ctrl-s confirms and Esc cancels
+    pub fn handle_key(&mut self, key: KeyEvent) -> EditorAction {
+        if key.kind != KeyEventKind::Press {
+            return EditorAction::None;
+        }
+
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+
+        match key.code {
+            KeyCode::Char('s') if ctrl => return EditorAction::Confirm,
+            KeyCode::Enter if alt => return EditorAction::Confirm,
+            KeyCode::Esc => return EditorAction::Cancel,
+
+            KeyCode::Enter => self.insert('\n'),
+            KeyCode::Char('w') if ctrl => self.delete_word(),
+            KeyCode::Char(chr) if !ctrl && !alt => self.insert(chr),
+
+            KeyCode::Backspace if ctrl || alt => self.delete_word(),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete => self.delete(),
+
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.buffer.len()),
+            KeyCode::Up => self.move_vertical(false, 1),
+            KeyCode::Down => self.move_vertical(true, 1),
+            KeyCode::PageUp => self.move_vertical(false, 10),
+            KeyCode::PageDown => self.move_vertical(true, 10),
+            KeyCode::Home if ctrl => self.cursor = 0,
+            KeyCode::End if ctrl => self.cursor = self.buffer.len(),
+            KeyCode::Home => self.row_home(),
+            KeyCode::End => self.row_end(),
+
+            _ => {}
+        }
+
+        EditorAction::None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor(text: &str, width: usize) -> TextEditor {
+        let mut editor = TextEditor::new();
+        editor.set_text(text);
+        editor.width = width;
+        editor
+    }
+
+    #[test]
+    fn wide_chars_are_counted_by_columns() {
+        assert_eq!(wrap_text("日本語テキスト", 6), ["日本語", "テキス", "ト"]);
+        // A wide char that would straddle the edge goes to the next row
+        assert_eq!(wrap_text("日本語テ", 5), ["日本", "語テ"]);
+    }
+
+    #[test]
+    fn words_longer_than_a_row_are_split() {
+        let chars: Vec<char> = "a abcdefgh".chars().collect();
+        assert_eq!(wrap_ranges(&chars, 4), [(0, 2), (2, 6), (6, 10)]);
+        assert_eq!(wrap_text("a abcdefgh", 4), ["a ", "abcd", "efgh"]);
+    }
+
+    #[test]
+    fn hard_breaks_start_a_row() {
+        assert_eq!(wrap_text("one\ntwo\n", 10), ["one", "two", ""]);
+        assert_eq!(wrap_text("\n\n", 10), ["", "", ""]);
+    }
+
+    #[test]
+    fn whitespace_hangs_at_a_soft_break() {
+        let chars: Vec<char> = "ab   cd".chars().collect();
+        assert_eq!(wrap_ranges(&chars, 4), [(0, 5), (5, 7)]);
+        // The hanging spaces are clipped to the row
+        assert_eq!(wrap_text("ab   cd", 4), ["ab  ", "cd"]);
+    }
+
+    #[test]
+    fn cursor_on_a_soft_break_belongs_to_the_next_row() {
+        let mut editor = editor("abc def", 4);
+        let rows = editor.rows();
+        assert_eq!(rows, [(0, 4), (4, 7)]);
+
+        editor.cursor = 4;
+        assert_eq!(editor.cursor_row(&rows), 1);
+        assert_eq!(editor.view(4, 5).1, 0);
+        assert_eq!(editor.view(4, 5).2, 1);
+
+        editor.cursor = 3;
+        assert_eq!(editor.cursor_row(&rows), 0);
+    }
+
+    #[test]
+    fn end_stays_before_the_hanging_space() {
+        let mut editor = editor("abc def", 4);
+        editor.cursor = 0;
+        editor.row_end();
+        assert_eq!(editor.cursor, 3);
+        assert_eq!(editor.cursor_row(&editor.rows()), 0);
+    }
+
+    #[test]
+    fn cursor_column_counts_wide_chars() {
+        let mut editor = editor("日本語", 10);
+        let (lines, col, row) = editor.view(10, 5);
+        assert_eq!(lines, ["日本語"]);
+        assert_eq!((col, row), (6, 0));
+    }
+}